@@ -0,0 +1,187 @@
+use chrono::{Days, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Timelike, Weekday};
+
+use crate::calendar::week_of;
+use crate::todoist::{local_datetime, Task};
+
+/// Minutes per row of the timeline.
+pub const SLOT_MINUTES: i64 = 30;
+pub const SLOTS_PER_DAY: usize = (24 * 60 / SLOT_MINUTES) as usize;
+/// Length assumed for timed tasks without a duration.
+const DEFAULT_MINUTES: i64 = 30;
+
+/// State of the agenda: the day shown on the timeline, whose week is shown
+/// in the grid, and the first half hour in view.
+#[derive(Debug)]
+pub struct Agenda {
+    pub date: NaiveDate,
+    pub first_slot: usize,
+    /// Rows that fit on screen, updated on every frame.
+    pub visible_slots: usize,
+    pub week_start: Weekday,
+}
+
+/// A timed task laid out on the timeline.
+#[derive(Debug, Clone, Copy)]
+pub struct TimeBlock<'a> {
+    pub task: &'a Task,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    /// Whether another block on the same day overlaps this one.
+    pub overlaps: bool,
+}
+
+impl TimeBlock<'_> {
+    pub fn covers(&self, start: NaiveDateTime, end: NaiveDateTime) -> bool {
+        self.start < end && self.end > start
+    }
+}
+
+impl Agenda {
+    /// Opens on the day of `now`, scrolled to the hour before it.
+    pub fn new(now: NaiveDateTime, week_start: Weekday) -> Self {
+        Self {
+            date: now.date(),
+            first_slot: slot_of(now.time()).saturating_sub(2),
+            visible_slots: SLOTS_PER_DAY,
+            week_start,
+        }
+    }
+
+    pub fn move_days(&mut self, days: i64) {
+        let date = if days < 0 {
+            self.date.checked_sub_days(Days::new(days.unsigned_abs()))
+        } else {
+            self.date.checked_add_days(Days::new(days as u64))
+        };
+        self.date = date.unwrap_or(self.date);
+    }
+
+    /// Scrolls the timeline by whole slots, keeping a screenful in view.
+    pub fn scroll(&mut self, slots: isize) {
+        let last = SLOTS_PER_DAY.saturating_sub(self.visible_slots);
+        self.first_slot = self.first_slot.saturating_add_signed(slots).min(last);
+    }
+
+    /// The week of the selected day, from `week_start` on.
+    pub fn week(&self) -> [NaiveDate; 7] {
+        let start = week_of(self.date, self.week_start);
+        std::array::from_fn(|i| start + Days::new(i as u64))
+    }
+}
+
+/// The timeline row a time of day falls into.
+pub fn slot_of(time: NaiveTime) -> usize {
+    (time.num_seconds_from_midnight() as i64 / 60 / SLOT_MINUTES) as usize
+}
+
+/// Start and end of a timeline row on `date`.
+pub fn slot_range(date: NaiveDate, slot: usize) -> (NaiveDateTime, NaiveDateTime) {
+    let start = date.and_time(NaiveTime::MIN) + TimeDelta::minutes(slot as i64 * SLOT_MINUTES);
+    (start, start + TimeDelta::minutes(SLOT_MINUTES))
+}
+
+/// When a task with a due time is due, in local time.
+pub fn due_datetime(task: &Task) -> Option<NaiveDateTime> {
+    local_datetime(task.due.as_ref()?.datetime.as_deref()?)
+}
+
+/// Open tasks due at a time taking up part of `date`, including ones
+/// running over from the day before, by start time, with overlapping blocks
+/// flagged.
+pub fn blocks_on(tasks: &[Task], date: NaiveDate) -> Vec<TimeBlock<'_>> {
+    let day_start = date.and_time(NaiveTime::MIN);
+    let day_end = day_start + TimeDelta::days(1);
+    let mut blocks: Vec<TimeBlock> = tasks
+        .iter()
+        .filter(|task| !task.is_completed)
+        .filter_map(|task| {
+            let start = due_datetime(task)?;
+            let minutes = task
+                .duration
+                .as_ref()
+                .map_or(DEFAULT_MINUTES, |duration| duration.minutes());
+            let block = TimeBlock {
+                task,
+                start,
+                end: start + TimeDelta::minutes(minutes.max(1)),
+                overlaps: false,
+            };
+            block.covers(day_start, day_end).then_some(block)
+        })
+        .collect();
+    blocks.sort_by_key(|block| block.start);
+
+    for i in 0..blocks.len() {
+        let (start, end) = (blocks[i].start, blocks[i].end);
+        blocks[i].overlaps = blocks
+            .iter()
+            .enumerate()
+            .any(|(j, other)| j != i && other.covers(start, end));
+    }
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::todoist::test_task;
+
+    #[test]
+    fn test_blocks_and_overlaps() {
+        let task = |id: &str, datetime: &str, minutes| test_task(id, Some(datetime), minutes);
+        let tasks = vec![
+            task("standup", "2024-03-04T09:00:00", Some(15)),
+            task("review", "2024-03-04T10:00:00", Some(90)),
+            task("lunch", "2024-03-04T11:00:00", None),
+            task("tomorrow", "2024-03-05T09:00:00", Some(60)),
+        ];
+        let date = NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+        let blocks = blocks_on(&tasks, date);
+
+        let summary: Vec<(&str, bool)> = blocks
+            .iter()
+            .map(|block| (block.task.id.as_str(), block.overlaps))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("standup", false), ("review", true), ("lunch", true)]
+        );
+
+        // The review spans three half hour rows
+        let rows = (0..SLOTS_PER_DAY)
+            .filter(|slot| {
+                let (start, end) = slot_range(date, *slot);
+                blocks[1].covers(start, end)
+            })
+            .count();
+        assert_eq!(rows, 3);
+
+        let mut agenda = Agenda::new(date.and_hms_opt(9, 10, 0).unwrap(), Weekday::Mon);
+        assert_eq!(agenda.first_slot, 16);
+        agenda.visible_slots = 20;
+        agenda.scroll(100);
+        assert_eq!(agenda.first_slot, SLOTS_PER_DAY - 20);
+        assert_eq!(agenda.week()[0], date);
+        agenda.week_start = Weekday::Sun;
+        assert_eq!(agenda.week()[0], date - Days::new(1));
+    }
+
+    #[test]
+    fn test_blocks_running_past_midnight() {
+        let tasks = vec![test_task("late", Some("2024-03-03T23:30:00"), Some(90))];
+        let date = NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+        let blocks = blocks_on(&tasks, date);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks_on(&tasks, date - Days::new(1)).len(), 1);
+        assert!(blocks_on(&tasks, date + Days::new(1)).is_empty());
+
+        // It fills the first two rows of the day
+        let rows: Vec<usize> = (0..SLOTS_PER_DAY)
+            .filter(|slot| {
+                let (start, end) = slot_range(date, *slot);
+                blocks[0].covers(start, end)
+            })
+            .collect();
+        assert_eq!(rows, vec![0, 1]);
+    }
+}
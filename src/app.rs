@@ -1,8 +1,25 @@
+use crate::agenda::{self, Agenda};
+use crate::calendar::{self, Calendar};
 use crate::config::ApiKeyManager;
-use crate::todoist::PendingChange::TaskCompletion;
-use crate::todoist::{PendingChange, Task, TodoistClient};
+use crate::input::TextInput;
+use crate::keymap::{Action, Key, Keymap, Mode};
+use crate::layout::{LayoutConfig, LayoutMode, Pane, PaneState, RenderedAreas};
+use crate::palette::PaletteState;
+use crate::profile::Profile;
+use crate::prompt::{match_project, parse_priority, Prompt, PromptKind};
+use crate::search::SearchState;
+use crate::settings::{self, DefaultView, Settings};
+use crate::sync::SyncMessage;
+use crate::theme::Theme;
+use crate::toast::{Severity, Toasts};
+use crate::todoist::PendingChange::{TaskCompletion, TaskDeletion, TaskMove};
+use crate::todoist::{self, KeyError, PendingChange, Project, Task, TaskUpdate, TodoistClient};
+use chrono::NaiveDate;
 use ratatui::widgets::ListState;
 use std::error;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::{oneshot, Notify};
 
 /// Application result type.
 pub type AppResult<T> = Result<T, Box<dyn error::Error>>;
@@ -23,53 +40,209 @@ impl std::fmt::Display for AppError {
 
 impl error::Error for AppError {}
 
+/// Most mutations kept for undo; older ones are forgotten.
+const MAX_HISTORY: usize = 100;
+
+/// A local task mutation that can be reverted with [`App::undo`] and
+/// reapplied with [`App::redo`].
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    /// What the mutation did, e.g. `Completed “Buy milk”`.
+    pub description: String,
+    /// Touched tasks as they were before, with their position in `App::tasks`.
+    pub before: Vec<(usize, Task)>,
+    /// Touched tasks afterwards; deleted tasks are missing.
+    pub after: Vec<Task>,
+    /// Changes queued by the mutation.
+    pub changes: Vec<PendingChange>,
+    /// Changes the last undo queued to revert `changes` that were already
+    /// sent, `None` where the change was still queued and simply dropped.
+    pub compensations: Vec<Option<PendingChange>>,
+}
+
+impl HistoryEntry {
+    fn remap_task_id(&mut self, old_id: &str, new_id: &str) {
+        let tasks = self.before.iter_mut().map(|(_, task)| task);
+        for task in tasks.chain(self.after.iter_mut()) {
+            if task.id == old_id {
+                task.id = new_id.to_string();
+            }
+        }
+        let compensations = self.compensations.iter_mut().flatten();
+        for change in self.changes.iter_mut().chain(compensations) {
+            change.remap_task_id(old_id, new_id);
+        }
+    }
+}
+
+/// What the status bar knows about the background sync.
+#[derive(Debug, Default)]
+pub struct SyncStatus {
+    /// Whether Todoist was reachable on the last request, `None` before any.
+    pub online: Option<bool>,
+    /// When tasks were last fetched successfully.
+    pub last_refresh: Option<Instant>,
+    /// The most recent sync error and when it happened.
+    pub last_error: Option<(String, Instant)>,
+}
+
+/// An API key being verified with Todoist before it is saved.
+#[derive(Debug)]
+pub struct KeyCheck {
+    pub api_key: String,
+    /// When the check started, to animate the spinner.
+    pub started: Instant,
+    result: oneshot::Receiver<Result<(), KeyError>>,
+}
+
 pub struct AppState {
     pub today_tasks: Vec<Task>,
     pub inbox_tasks: Vec<Task>,
     pub pending_tasks: Vec<PendingChange>,
 }
 
+/// How often the settings file is checked for changes.
+const SETTINGS_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
 /// Application.
 pub struct App {
     /// Is the application running?
     pub running: bool,
+    /// Quitting was held back by unsent changes; quitting again discards
+    /// them
+    pub quit_requested: bool,
     /// counter
     pub counter: u8,
     pub list_state: ListState,
     pub today_list_state: ListState,
     /// Is onboarding complete?
     pub onboarding_complete: bool,
+    /// Are the saved keys waiting for the vault passphrase?
+    pub vault_locked: bool,
     /// Input buffer for onboarding
-    pub input_buffer: String,
+    pub input_buffer: TextInput,
     /// Temporary storage for API key
     pub api_key: Option<String>,
+    /// Entered API key waiting for Todoist to accept it
+    pub key_check: Option<KeyCheck>,
+    /// Why the last entered API key was not accepted
+    pub key_error: Option<KeyError>,
+    /// Profile whose key, settings and cached tasks are in use
+    pub profile: Profile,
+    /// Changes with every profile switch or logout, so sync results meant
+    /// for the previous session are dropped
+    pub session: u64,
     /// API key manager
     pub api_key_manager: ApiKeyManager,
     /// Todoist client
     pub todoist_client: Option<TodoistClient>,
     /// Inbox tasks from Todoist
     pub tasks: Vec<Task>,
+    /// Projects from Todoist, used to resolve `Task::project_id`
+    pub projects: Vec<Project>,
+    /// Active `/` search, if any
+    pub search: Option<SearchState>,
     /// Pending changes to sync
     pub pending_changes: Vec<PendingChange>,
+    /// Settings from the profile's settings file
+    pub settings: Settings,
+    /// When the settings file was changed as of the last load, to reload
+    /// it when edited
+    pub settings_modified: Option<SystemTime>,
+    /// When the settings file was last checked for changes
+    pub settings_checked: Instant,
     pub refresh_interval: u64,
     pub app_state: AppState,
+    /// Selected task of the focused pane; list states follow it by id
     pub selected_task: Option<String>,
+    /// Pane holding the cursor
+    pub focused: Pane,
+    /// Selected task of the other pane, restored when it gets the focus
+    pub other_selection: Option<String>,
+    /// Active key bindings
+    pub keymap: Keymap,
+    /// Keys of a chord typed so far, e.g. the first `g` of `g g`
+    pub pending_keys: Vec<Key>,
+    /// Is the help overlay shown?
+    pub show_help: bool,
+    /// Are vim-style bindings and counts enabled?
+    pub vim_mode: bool,
+    /// Count typed before a command in vim mode, e.g. the 5 in `5j`
+    pub count: Option<usize>,
+    /// Command typed after `:`, while the command line is open
+    pub command_line: Option<TextInput>,
+    /// One-line feedback shown at the bottom of the screen
+    pub message: Option<String>,
+    /// Task mutations that can be undone, most recent last
+    pub history: Vec<HistoryEntry>,
+    /// Undone mutations that can be redone, most recently undone last
+    pub redo_stack: Vec<HistoryEntry>,
+    /// Open task prompt, e.g. for editing or rescheduling
+    pub prompt: Option<Prompt>,
+    /// Tasks marked for a bulk action, in the order they were marked
+    pub marked: Vec<String>,
+    /// Where a range selection started in the focused list, while marking one
+    pub visual_anchor: Option<usize>,
+    /// Active color theme
+    pub theme: Theme,
+    /// Built-in and user themes to switch between
+    pub themes: Vec<Theme>,
+    /// Pane preferences from the settings file
+    pub layout: LayoutConfig,
+    /// How the panes are arranged at the current terminal size
+    pub layout_mode: LayoutMode,
+    /// Terminal width and height, to arrange the panes of another profile
+    pub terminal_size: (u16, u16),
+    /// Panes hidden or zoomed by the user
+    pub panes: PaneState,
+    /// Clickable areas of the last frame, for mouse hit-testing
+    pub rendered: RenderedAreas,
+    /// Open command palette, if any
+    pub palette: Option<PaletteState>,
+    /// Open month view, if any
+    pub calendar: Option<Calendar>,
+    /// Open day and week timeline, if any
+    pub agenda: Option<Agenda>,
+    /// Wakes the background refresh loop ahead of its interval
+    pub refresh_requested: Arc<Notify>,
+    /// Wakes the background sync to send queued changes right away
+    pub flush_requested: Arc<Notify>,
+    /// Quit or logout waiting for queued changes to be sent first
+    pub after_flush: Option<Action>,
+    /// State of the background sync, shown in the status bar
+    pub sync_status: SyncStatus,
+    /// Transient notifications, expired on every tick
+    pub toasts: Toasts,
 }
 
-impl Default for App {
-    fn default() -> Self {
-        Self {
+impl App {
+    /// Constructs a new instance of [`App`] for a profile, showing its
+    /// cached tasks until the first refresh.
+    pub fn new(profile: Profile) -> Self {
+        let settings = Settings::default();
+        let mut app = Self {
             running: true,
+            quit_requested: false,
             counter: 0,
             list_state: ListState::default(),
             today_list_state: ListState::default(),
             onboarding_complete: false,
-            input_buffer: String::new(),
+            vault_locked: false,
+            input_buffer: TextInput::masked(),
             api_key: None,
-            api_key_manager: ApiKeyManager::new(),
+            key_check: None,
+            key_error: None,
+            api_key_manager: profile.key_manager(),
+            profile,
+            session: 0,
             todoist_client: None,
             tasks: Vec::new(),
-            refresh_interval: 10, // Default to 10 seconds
+            projects: Vec::new(),
+            search: None,
+            refresh_interval: settings.refresh_interval,
+            settings,
+            settings_modified: None,
+            settings_checked: Instant::now(),
             pending_changes: Vec::new(),
             app_state: AppState {
                 today_tasks: Vec::new(),
@@ -77,32 +250,471 @@ impl Default for App {
                 pending_tasks: Vec::new(),
             },
             selected_task: None,
+            focused: Pane::Today,
+            other_selection: None,
+            keymap: Keymap::default(),
+            pending_keys: Vec::new(),
+            show_help: false,
+            vim_mode: false,
+            count: None,
+            command_line: None,
+            message: None,
+            history: Vec::new(),
+            redo_stack: Vec::new(),
+            prompt: None,
+            marked: Vec::new(),
+            visual_anchor: None,
+            theme: Theme::default(),
+            themes: Theme::BUILTIN
+                .iter()
+                .filter_map(|name| Theme::builtin(name))
+                .collect(),
+            layout: LayoutConfig::default(),
+            layout_mode: LayoutMode::Split,
+            terminal_size: (0, 0),
+            panes: PaneState::default(),
+            rendered: RenderedAreas::default(),
+            palette: None,
+            calendar: None,
+            agenda: None,
+            refresh_requested: Arc::new(Notify::new()),
+            flush_requested: Arc::new(Notify::new()),
+            after_flush: None,
+            sync_status: SyncStatus::default(),
+            toasts: Toasts::default(),
+        };
+        // Saved keys are only readable once the vault is unlocked
+        app.vault_locked = app.api_key_manager.is_locked();
+        if !app.vault_locked {
+            app.load_api_key();
+        }
+        if let Some(cache) = app.profile.load_cache() {
+            app.tasks = cache.tasks;
+            app.projects = cache.projects;
+            app.sync_selection();
+        }
+
+        app
+    }
+
+    /// Replaces the session with another profile, created if it does not
+    /// exist yet: its key, settings and cached tasks are loaded and a
+    /// refresh is requested.
+    ///
+    /// Refused while changes are queued, since they belong to the account
+    /// of the current profile.
+    pub fn switch_profile(&mut self, name: &str) -> Result<(), String> {
+        if name == self.profile.name {
+            return Ok(());
+        }
+        if !self.pending_changes.is_empty() {
+            return Err("Changes are still waiting to sync, try again once sent".to_string());
         }
+        self.open_profile(self.profile.sibling(name)?)?;
+        self.toasts
+            .push(Severity::Info, format!("Profile: {}", self.profile.name));
+        Ok(())
     }
-}
 
-impl App {
-    /// Constructs a new instance of [`App`].
-    pub fn new() -> Self {
-        let mut app = Self::default();
-        // Check if we have a saved API key
-        if let Ok(key) = app.api_key_manager.load_api_key("todoist") {
-            app.api_key = Some(key.clone());
-            app.todoist_client = Some(TodoistClient::new(key));
-            app.onboarding_complete = true;
-
-            // Load refresh interval from config if available
-            if let Ok(config) = app.api_key_manager.load_config() {
-                app.refresh_interval = config.refresh_interval()
+    /// Starts a fresh session for `profile`, keeping only what belongs to
+    /// the terminal rather than the account.
+    fn open_profile(&mut self, profile: Profile) -> Result<(), String> {
+        profile.key_manager().check_config()?;
+        let mut next = App::new(profile);
+        next.load_settings()?;
+        let (width, height) = self.terminal_size;
+        next.resize(width, height);
+        next.open_default_view();
+        next.session = self.session + 1;
+        next.refresh_requested = Arc::clone(&self.refresh_requested);
+        next.flush_requested = Arc::clone(&self.flush_requested);
+        next.toasts = std::mem::take(&mut self.toasts);
+        next.toasts.notifications = next.settings.notifications.clone();
+        *self = next;
+        self.refresh_requested.notify_one();
+        Ok(())
+    }
+
+    /// Quits or logs out once the queued changes were sent. The background
+    /// sync sends them, so the app keeps responding while Todoist is slow
+    /// or unreachable; if sending fails the action warns or is refused.
+    pub fn flush_then(&mut self, action: Action) {
+        if self.pending_changes.is_empty() || self.todoist_client.is_none() || self.quit_requested {
+            self.run_after_flush(action);
+            return;
+        }
+        self.after_flush = Some(action);
+        self.flush_requested.notify_one();
+        self.toasts.push(
+            Severity::Info,
+            format!("Sending {} changes…", self.pending_changes.len()),
+        );
+    }
+
+    fn run_after_flush(&mut self, action: Action) {
+        match action {
+            Action::Logout => {
+                if let Err(e) = self.logout() {
+                    self.toasts
+                        .push(Severity::Error, format!("Logout failed: {}", e));
+                }
             }
+            _ => self.request_quit(),
         }
+    }
 
-        app
+    /// Stops the app unless changes are still waiting to be sent, in which
+    /// case it warns first and only stops when asked again.
+    pub fn request_quit(&mut self) {
+        if self.pending_changes.is_empty() || self.quit_requested {
+            self.quit();
+            return;
+        }
+        self.quit_requested = true;
+        self.toasts.push(
+            Severity::Warning,
+            format!(
+                "{} unsent changes, quit again to discard them",
+                self.pending_changes.len()
+            ),
+        );
+    }
+
+    /// Removes the profile's API key and cached tasks and returns to
+    /// onboarding.
+    ///
+    /// Refused while changes are queued, they would be lost; send them
+    /// first.
+    pub fn logout(&mut self) -> Result<(), String> {
+        if !self.pending_changes.is_empty() {
+            return Err("Changes are still waiting to sync, try again once sent".to_string());
+        }
+        self.api_key_manager.remove_api_key("todoist")?;
+        self.profile.clear_cache()?;
+        self.open_profile(self.profile.clone())?;
+        self.toasts.push(Severity::Info, "Logged out");
+        Ok(())
+    }
+
+    /// Shows the onboarding screen to replace the saved API key. The
+    /// current key stays in use until Todoist accepts the new one.
+    pub fn change_api_key(&mut self) {
+        self.input_buffer.clear();
+        self.key_error = None;
+        self.onboarding_complete = false;
+    }
+
+    /// Uses the saved API key, if there is one.
+    fn load_api_key(&mut self) {
+        if let Ok(key) = self.api_key_manager.load_api_key("todoist") {
+            self.api_key = Some(key.clone());
+            self.todoist_client = Some(TodoistClient::new(key));
+            self.onboarding_complete = true;
+        }
+    }
+
+    /// Unlocks the vault with its passphrase and loads the saved API key.
+    pub fn unlock(&mut self, passphrase: &str) -> Result<(), String> {
+        self.api_key_manager.unlock(passphrase)?;
+        self.vault_locked = false;
+        self.load_api_key();
+        self.refresh_requested.notify_one();
+        Ok(())
+    }
+
+    /// Loads the profile's settings file.
+    ///
+    /// Fails on invalid values, unknown actions, unparsable keys or
+    /// conflicting bindings so the problem is reported before the terminal
+    /// switches screens.
+    pub fn load_settings(&mut self) -> Result<(), String> {
+        let path = self.profile.settings_path();
+        let settings = Settings::load(&path);
+        // Recorded even for an invalid file, so it is reported once
+        self.settings_modified = settings::modified(&path);
+        self.apply_settings(settings?)
+            .map_err(|e| format!("{}: {}", settings::file_name(&path), e))
+    }
+
+    /// Switches to new settings, leaving everything as it was if any of
+    /// them is invalid.
+    pub fn apply_settings(&mut self, settings: Settings) -> Result<(), String> {
+        settings.validate()?;
+        let base = if settings.vim_mode {
+            Keymap::vim()
+        } else {
+            Keymap::default()
+        };
+        let keymap = base
+            .with_overrides(&settings.keymap)
+            .map_err(|e| format!("`keymap`: {}", e))?;
+        let themes = Theme::all(&settings.themes).map_err(|e| format!("`themes`: {}", e))?;
+        // Without a theme set, keep the current one if it still exists
+        let name = settings.theme.as_deref().unwrap_or(&self.theme.name);
+        let theme = match themes.iter().find(|theme| theme.name == name) {
+            Some(theme) => theme.clone(),
+            None if settings.theme.is_none() => Theme::default(),
+            None => {
+                let names: Vec<&str> = themes.iter().map(|theme| theme.name.as_str()).collect();
+                return Err(format!(
+                    "`theme`: unknown theme `{}`, available: {}",
+                    name,
+                    names.join(", ")
+                ));
+            }
+        };
+
+        self.keymap = keymap;
+        self.vim_mode = settings.vim_mode;
+        self.themes = themes;
+        self.theme = theme;
+        self.layout = settings.layout.clone();
+        let (width, height) = self.terminal_size;
+        self.layout_mode = self.layout.mode(width, height);
+        self.refresh_interval = settings.refresh_interval;
+        self.toasts.notifications = settings.notifications.clone();
+        let week_start = settings.week_start.weekday();
+        if let Some(calendar) = &mut self.calendar {
+            calendar.week_start = week_start;
+        }
+        if let Some(agenda) = &mut self.agenda {
+            agenda.week_start = week_start;
+        }
+        self.settings = settings;
+        Ok(())
+    }
+
+    /// Reloads the settings file if it was changed since it was loaded,
+    /// keeping the current settings if the new ones are invalid. The
+    /// refresh loop picks up a new interval by itself.
+    pub fn reload_settings(&mut self) {
+        if settings::modified(&self.profile.settings_path()) == self.settings_modified {
+            return;
+        }
+        match self.load_settings() {
+            Ok(()) => self.toasts.push(Severity::Info, "Settings reloaded"),
+            Err(e) => self.toasts.push(
+                Severity::Error,
+                format!("Settings not applied, keeping the previous ones: {}", e),
+            ),
+        }
+    }
+
+    /// Shows the view picked in the settings, called on start.
+    pub fn open_default_view(&mut self) {
+        match self.settings.default_view {
+            DefaultView::Today => self.focus_pane(Pane::Today),
+            DefaultView::Inbox => self.focus_pane(Pane::Inbox),
+            DefaultView::Calendar => self.open_calendar(),
+            DefaultView::Agenda => self.open_agenda(),
+        }
+    }
+
+    /// Picks the pane arrangement for a new terminal size.
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.terminal_size = (width, height);
+        self.layout_mode = self.layout.mode(width, height);
+    }
+
+    pub fn focused_pane(&self) -> Pane {
+        self.focused
+    }
+
+    /// Moves the cursor to a pane, showing it again if it was hidden. The
+    /// pane's cursor returns to the task it was on when the pane was left.
+    pub fn focus_pane(&mut self, pane: Pane) {
+        self.end_range();
+        if self.panes.hidden == Some(pane) {
+            self.panes.hidden = None;
+        }
+        if pane != self.focused {
+            self.focused = pane;
+            std::mem::swap(&mut self.selected_task, &mut self.other_selection);
+        }
+        self.sync_selection();
+    }
+
+    /// Shows only the focused pane, or all of them again.
+    pub fn toggle_zoom(&mut self) {
+        self.panes.zoomed = !self.panes.zoomed;
+    }
+
+    /// Hides the focused pane and focuses the other one, or shows a hidden
+    /// pane again.
+    pub fn toggle_hidden(&mut self) {
+        if self.panes.hidden.is_some() {
+            self.panes.hidden = None;
+        } else {
+            let focused = self.focused_pane();
+            self.focus_pane(focused.other());
+            self.panes.hidden = Some(focused);
+            self.panes.zoomed = false;
+        }
+    }
+
+    fn find_theme(&self, name: &str) -> Result<Theme, String> {
+        self.themes
+            .iter()
+            .find(|theme| theme.name == name)
+            .cloned()
+            .ok_or_else(|| {
+                let names: Vec<&str> = self
+                    .themes
+                    .iter()
+                    .map(|theme| theme.name.as_str())
+                    .collect();
+                format!("unknown theme `{}`, available: {}", name, names.join(", "))
+            })
+    }
+
+    /// Switches to a theme by name and remembers it for the next start.
+    pub fn set_theme(&mut self, name: &str) -> Result<(), String> {
+        self.theme = self.find_theme(name)?;
+        self.settings.theme = Some(name.to_string());
+        let path = self.profile.settings_path();
+        let saved = settings::save_theme(&path, name);
+        // Our own change needs no reload
+        self.settings_modified = settings::modified(&path);
+        if let Err(e) = saved {
+            self.toasts
+                .push(Severity::Warning, format!("Theme not saved: {}", e));
+        }
+        self.toasts
+            .push(Severity::Info, format!("Theme: {}", self.theme.name));
+        Ok(())
+    }
+
+    /// Switches to the theme after the active one.
+    pub fn next_theme(&mut self) {
+        let current = self
+            .themes
+            .iter()
+            .position(|theme| theme.name == self.theme.name);
+        let next = current.map_or(0, |i| (i + 1) % self.themes.len());
+        if let Some(name) = self.themes.get(next).map(|theme| theme.name.clone()) {
+            let _ = self.set_theme(&name);
+        }
+    }
+
+    /// Applies a result of the background sync.
+    pub fn apply_sync_message(&mut self, message: SyncMessage) {
+        let now = Instant::now();
+        let was_offline = self.sync_status.online == Some(false);
+        match message {
+            SyncMessage::Refreshed { tasks, projects } => {
+                self.tasks = tasks;
+                if let Some(projects) = projects {
+                    self.projects = projects;
+                }
+                // The fetch may predate changes still waiting in the queue
+                self.apply_pending_changes_locally();
+                let tasks = &self.tasks;
+                self.marked.retain(|id| {
+                    tasks
+                        .iter()
+                        .any(|task| task.id == *id && !task.is_completed)
+                });
+                self.update_search();
+                self.sync_selection();
+                self.sync_status.online = Some(true);
+                self.sync_status.last_refresh = Some(now);
+                self.sync_status.last_error = None;
+                if was_offline {
+                    self.toasts.push(Severity::Success, "Back online");
+                }
+            }
+            SyncMessage::Sent { .. } => {
+                self.sync_status.online = Some(true);
+                if was_offline {
+                    self.toasts.push(Severity::Success, "Back online");
+                }
+                // Changes queued meanwhile are sent before quitting too
+                match self.after_flush {
+                    Some(action) if self.pending_changes.is_empty() => {
+                        self.after_flush = None;
+                        self.run_after_flush(action);
+                    }
+                    Some(_) => self.flush_requested.notify_one(),
+                    None => {}
+                }
+            }
+            SyncMessage::Created { old_id, task } => self.remap_task_id(&old_id, &task.id),
+            SyncMessage::Unsent { mut changes } => {
+                changes.append(&mut self.pending_changes);
+                self.pending_changes = changes;
+            }
+            SyncMessage::Failed { error, offline } => {
+                // Retries while offline fail every few seconds, only
+                // announce the first one
+                if !offline {
+                    self.toasts
+                        .push(Severity::Error, format!("Sync failed: {}", error));
+                } else if !was_offline {
+                    self.toasts
+                        .push(Severity::Warning, "Todoist unreachable, retrying");
+                }
+                self.sync_status.online = Some(!offline);
+                self.sync_status.last_error = Some((error, now));
+                if let Some(action) = self.after_flush.take() {
+                    self.run_after_flush(action);
+                }
+            }
+        }
+    }
+
+    fn apply_pending_changes_locally(&mut self) {
+        for change in &self.pending_changes {
+            change.apply_locally(&mut self.tasks);
+        }
+    }
+
+    /// Replaces the id of a recreated task with the one Todoist assigned.
+    fn remap_task_id(&mut self, old_id: &str, new_id: &str) {
+        for task in &mut self.tasks {
+            if task.id == old_id {
+                task.id = new_id.to_string();
+            }
+        }
+        for change in &mut self.pending_changes {
+            change.remap_task_id(old_id, new_id);
+        }
+        for entry in self.history.iter_mut().chain(self.redo_stack.iter_mut()) {
+            entry.remap_task_id(old_id, new_id);
+        }
+        for selected in [&mut self.selected_task, &mut self.other_selection] {
+            if selected.as_deref() == Some(old_id) {
+                *selected = Some(new_id.to_string());
+            }
+        }
+    }
+
+    /// The mode key presses are currently routed to, topmost overlay first.
+    pub fn mode(&self) -> Mode {
+        if !self.onboarding_complete {
+            Mode::Onboarding
+        } else if self.show_help {
+            Mode::Help
+        } else if self.search.is_some() {
+            Mode::Search
+        } else if self.palette.is_some() {
+            Mode::Palette
+        } else if self.prompt.is_some() {
+            Mode::Prompt
+        } else if self.command_line.is_some() {
+            Mode::CommandLine
+        } else if self.calendar.is_some() {
+            Mode::Calendar
+        } else if self.agenda.is_some() {
+            Mode::Agenda
+        } else {
+            Mode::List
+        }
     }
 
     pub fn today_tasks(&self) -> Vec<&Task> {
         self.tasks
             .iter()
+            .filter(|task| !task.is_completed)
             .filter(|task| {
                 if let Some(due) = &task.due {
                     due.date == chrono::Local::now().date_naive().to_string()
@@ -113,8 +725,109 @@ impl App {
             .collect()
     }
 
+    pub fn inbox_tasks(&self) -> Vec<&Task> {
+        self.tasks
+            .iter()
+            .filter(|task| !task.is_completed)
+            .collect()
+    }
+
+    /// Opens the `/` search overlay with an empty query.
+    pub fn open_search(&mut self) {
+        self.search = Some(SearchState::default());
+    }
+
+    /// Re-runs the active search after the query changed.
+    pub fn update_search(&mut self) {
+        if let Some(search) = &mut self.search {
+            search.update(&self.tasks, &self.projects);
+        }
+    }
+
+    /// Closes the search overlay and moves selection to the chosen task.
+    pub fn confirm_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            if let Some(result) = search.selected() {
+                self.focus_task(&result.task_id);
+            }
+        }
+    }
+
+    /// Selects a task in its home view: Today if it is due today, Inbox otherwise.
+    pub fn focus_task(&mut self, task_id: &str) {
+        let Some(pane) = [Pane::Today, Pane::Inbox]
+            .into_iter()
+            .find(|pane| self.pane_tasks(*pane).iter().any(|task| task.id == task_id))
+        else {
+            return;
+        };
+        self.focus_pane(pane);
+        self.selected_task = Some(task_id.to_string());
+        self.sync_selection();
+    }
+
     /// Handles the tick event of the terminal.
-    pub async fn tick(&mut self) {}
+    pub async fn tick(&mut self) {
+        let now = Instant::now();
+        self.toasts.expire(now);
+        self.poll_key_check();
+        if now.duration_since(self.settings_checked) >= SETTINGS_POLL_INTERVAL {
+            self.settings_checked = now;
+            self.reload_settings();
+        }
+    }
+
+    /// Verifies the entered API key in the background; it is only saved
+    /// once Todoist accepts it.
+    pub fn check_api_key(&mut self) {
+        let api_key = self.input_buffer.value().trim().to_string();
+        let client = TodoistClient::new(api_key.clone());
+        let (tx, result) = oneshot::channel();
+        tokio::spawn(async move {
+            let _ = tx.send(client.verify_key().await);
+        });
+        self.key_error = None;
+        self.key_check = Some(KeyCheck {
+            api_key,
+            started: Instant::now(),
+            result,
+        });
+    }
+
+    /// Saves the key once the check succeeds, or keeps the input so it can
+    /// be corrected and submitted again.
+    fn poll_key_check(&mut self) {
+        let Some(check) = &mut self.key_check else {
+            return;
+        };
+        let result = match check.result.try_recv() {
+            Ok(result) => result,
+            Err(oneshot::error::TryRecvError::Empty) => return,
+            Err(oneshot::error::TryRecvError::Closed) => {
+                Err(KeyError::Other("the check was interrupted".to_string()))
+            }
+        };
+        let api_key = std::mem::take(&mut check.api_key);
+        self.key_check = None;
+        match result {
+            Ok(()) => self.save_api_key(api_key),
+            Err(e) => self.key_error = Some(e),
+        }
+    }
+
+    fn save_api_key(&mut self, api_key: String) {
+        if let Err(e) = self.api_key_manager.save_api_key("todoist", &api_key) {
+            self.toasts
+                .push(Severity::Error, format!("Failed to save API key: {}", e));
+            return;
+        }
+        self.input_buffer.clear();
+        self.todoist_client = Some(TodoistClient::new(api_key.clone()));
+        self.api_key = Some(api_key);
+        self.onboarding_complete = true;
+        self.refresh_requested.notify_one();
+        self.toasts.push(Severity::Success, "API key saved");
+    }
 
     /// Validate API key format
     pub fn is_valid_api_key(&self) -> bool {
@@ -138,151 +851,843 @@ impl App {
         }
     }
 
+    /// Selects the next task of the focused list, wrapping around.
     pub fn next(&mut self) {
-        if self.list_state.selected().is_some() {
-            let i = match self.list_state.selected() {
-                Some(i) => {
-                    if i >= self.tasks.len().saturating_sub(1) {
-                        0
-                    } else {
-                        i + 1
-                    }
-                }
-                None => 0,
-            };
-            self.list_state.select(Some(i));
-            self.selected_task = Some(self.tasks[i].id.clone());
-        } else {
-            let today_tasks_count = self
-                .app_state
-                .today_tasks
-                .iter()
-                .filter(|task| {
-                    if let Some(due) = &task.due {
-                        let today = chrono::Local::now().date_naive();
-                        let task_date =
-                            chrono::NaiveDate::parse_from_str(&due.date, "%Y-%m-%d").ok();
-                        task_date == Some(today)
-                    } else {
-                        false
-                    }
-                })
-                .count();
-
-            let i = match self.today_list_state.selected() {
-                Some(i) => {
-                    if i >= today_tasks_count.saturating_sub(1) {
-                        0
-                    } else {
-                        i + 1
-                    }
-                }
-                None => 0,
-            };
-            self.today_list_state.select(Some(i));
-            let selected_task_id = self.today_tasks().get(i).unwrap().id.clone();
-            self.selected_task = self
-                .tasks
-                .iter()
-                .filter(|task| task.id == selected_task_id)
-                .map(|task| task.id.clone())
-                .next();
+        let count = self.focused_tasks().len();
+        match self.selected_index() {
+            Some(i) if i + 1 < count => self.select_index(i + 1),
+            _ => self.select_first(),
         }
     }
 
     pub async fn toggle_task_completion(&mut self, task_id: String) -> AppResult<()> {
-        let task_pos = self.tasks.iter().position(|task| task.id == task_id);
-        if let Some(task) = &mut self
+        self.toggle_tasks_completion(&[task_id])
+    }
+
+    /// Completes tasks, or reopens them if all of them are completed.
+    pub fn toggle_tasks_completion(&mut self, task_ids: &[String]) -> AppResult<()> {
+        let mut all_completed = true;
+        for task_id in task_ids {
+            all_completed &= self.find_task(task_id)?.is_completed;
+        }
+        let completed = !all_completed;
+        let verb = if completed { "Completed" } else { "Reopened" };
+        let description = format!("{} {}", verb, self.describe(task_ids)?);
+        let changes = task_ids
+            .iter()
+            .map(|task_id| TaskCompletion {
+                task_id: task_id.clone(),
+                completed,
+            })
+            .collect();
+        self.perform(description, changes);
+        Ok(())
+    }
+
+    /// Deletes a task locally and queues the deletion for sync.
+    pub fn delete_task(&mut self, task_id: String) -> AppResult<()> {
+        self.delete_tasks(&[task_id])
+    }
+
+    pub fn delete_tasks(&mut self, task_ids: &[String]) -> AppResult<()> {
+        let description = format!("Deleted {}", self.describe(task_ids)?);
+        let changes = task_ids
+            .iter()
+            .map(|task_id| TaskDeletion {
+                task_id: task_id.clone(),
+            })
+            .collect();
+        self.perform(description, changes);
+        Ok(())
+    }
+
+    /// Replaces the content of a task.
+    pub fn edit_task(&mut self, task_id: String, content: String) -> AppResult<()> {
+        let description = format!("Edited “{}”", self.find_task(&task_id)?.content);
+        let update = TaskUpdate {
+            content: Some(content),
+            ..Default::default()
+        };
+        self.perform(
+            description,
+            vec![PendingChange::TaskUpdate { task_id, update }],
+        );
+        Ok(())
+    }
+
+    /// Sets the due date of tasks from a Todoist date string such as
+    /// `tomorrow`; `no date` removes it.
+    pub fn reschedule_tasks(&mut self, task_ids: &[String], due_string: &str) -> AppResult<()> {
+        let description = format!("Rescheduled {} to {}", self.describe(task_ids)?, due_string);
+        let update = TaskUpdate {
+            due_string: Some(due_string.to_string()),
+            ..Default::default()
+        };
+        let changes = task_ids
+            .iter()
+            .map(|task_id| PendingChange::TaskUpdate {
+                task_id: task_id.clone(),
+                update: update.clone(),
+            })
+            .collect();
+        self.perform(description, changes);
+        Ok(())
+    }
+
+    /// Moves a task to another day, keeping its time of day if it has one.
+    pub fn move_to_day(&mut self, task_id: &str, date: NaiveDate) -> AppResult<()> {
+        let task = self.find_task(task_id)?;
+        let description = format!("Rescheduled “{}” to {}", task.content, date);
+        let datetime = agenda::due_datetime(task).and_then(|due| {
+            let moved = date.and_time(due.time());
+            todoist::utc_datetime(&moved.format("%Y-%m-%dT%H:%M:%S").to_string())
+        });
+        let update = match datetime {
+            Some(datetime) => TaskUpdate {
+                due_datetime: Some(datetime),
+                ..Default::default()
+            },
+            None => TaskUpdate {
+                due_date: Some(date.to_string()),
+                ..Default::default()
+            },
+        };
+        self.perform(
+            description,
+            vec![PendingChange::TaskUpdate {
+                task_id: task_id.to_string(),
+                update,
+            }],
+        );
+        Ok(())
+    }
+
+    /// Moves tasks to another project.
+    pub fn move_tasks(&mut self, task_ids: &[String], project: &Project) -> AppResult<()> {
+        let description = format!("Moved {} to #{}", self.describe(task_ids)?, project.name);
+        let changes = task_ids
+            .iter()
+            .map(|task_id| TaskMove {
+                task_id: task_id.clone(),
+                project_id: project.id.clone(),
+            })
+            .collect();
+        self.perform(description, changes);
+        Ok(())
+    }
+
+    /// Sets the Todoist priority of tasks, 4 being the most urgent.
+    pub fn set_priority(&mut self, task_ids: &[String], priority: u8) -> AppResult<()> {
+        let description = format!("Set {} to p{}", self.describe(task_ids)?, 5 - priority);
+        let update = TaskUpdate {
+            priority: Some(priority),
+            ..Default::default()
+        };
+        let changes = task_ids
+            .iter()
+            .map(|task_id| PendingChange::TaskUpdate {
+                task_id: task_id.clone(),
+                update: update.clone(),
+            })
+            .collect();
+        self.perform(description, changes);
+        Ok(())
+    }
+
+    /// Adds a label to tasks that do not have it yet.
+    pub fn add_label(&mut self, task_ids: &[String], label: &str) -> AppResult<()> {
+        let description = format!("Labeled {} @{}", self.describe(task_ids)?, label);
+        let mut changes = Vec::new();
+        for task_id in task_ids {
+            let task = self.find_task(task_id)?;
+            if task.labels.iter().any(|existing| existing == label) {
+                continue;
+            }
+            let mut labels = task.labels.clone();
+            labels.push(label.to_string());
+            changes.push(PendingChange::TaskUpdate {
+                task_id: task_id.clone(),
+                update: TaskUpdate {
+                    labels: Some(labels),
+                    ..Default::default()
+                },
+            });
+        }
+        self.perform(description, changes);
+        Ok(())
+    }
+
+    fn find_task(&self, task_id: &str) -> AppResult<&Task> {
+        Ok(self
             .tasks
-            .iter_mut()
-            .filter(|task| task.id == *task_id)
-            .next()
-        {
-            task.is_completed = !task.is_completed;
-            self.pending_changes.push(TaskCompletion {
-                task_id: task.id.clone(),
-                completed: task.is_completed,
+            .iter()
+            .find(|task| task.id == task_id)
+            .ok_or(AppError::TaskNotFound(task_id.to_string()))?)
+    }
+
+    /// Names a single task by its content and several by their number.
+    fn describe(&self, task_ids: &[String]) -> AppResult<String> {
+        match task_ids {
+            [task_id] => Ok(format!("“{}”", self.find_task(task_id)?.content)),
+            _ => Ok(format!("{} tasks", task_ids.len())),
+        }
+    }
+
+    /// Applies changes locally, queues them for sync and records them as a
+    /// single step in the undo history.
+    pub fn perform(&mut self, description: String, changes: Vec<PendingChange>) {
+        if changes.is_empty() {
+            return;
+        }
+        let touched = |task: &Task| changes.iter().any(|change| change.task_id() == task.id);
+        let before: Vec<(usize, Task)> = self
+            .tasks
+            .iter()
+            .enumerate()
+            .filter(|(_, task)| touched(task))
+            .map(|(index, task)| (index, task.clone()))
+            .collect();
+        for change in &changes {
+            change.apply_locally(&mut self.tasks);
+        }
+        let after: Vec<Task> = self
+            .tasks
+            .iter()
+            .filter(|task| touched(task))
+            .cloned()
+            .collect();
+
+        self.toasts
+            .push_with_action(Severity::Success, description.clone(), Some(Action::Undo));
+        self.pending_changes.extend(changes.iter().cloned());
+        self.history.push(HistoryEntry {
+            description,
+            before,
+            after,
+            changes,
+            compensations: Vec::new(),
+        });
+        if self.history.len() > MAX_HISTORY {
+            self.history.remove(0);
+        }
+        self.redo_stack.clear();
+        self.clear_marks();
+        // Completed tasks stay in `tasks` but drop out of the visible
+        // lists, so move the selection to whatever took their place.
+        self.sync_selection();
+    }
+
+    /// Reverts the most recent task mutation.
+    ///
+    /// Changes that are still queued are simply dropped. Ones that were
+    /// already sent are reverted by queueing a compensating change, such as
+    /// reopening a completed task or recreating a deleted one.
+    pub fn undo(&mut self) {
+        let Some(mut entry) = self.history.pop() else {
+            self.toasts.push(Severity::Warning, "Nothing to undo");
+            return;
+        };
+
+        entry.compensations = entry
+            .changes
+            .iter()
+            .map(|change| {
+                if let Some(pos) = self
+                    .pending_changes
+                    .iter()
+                    .position(|queued| queued == change)
+                {
+                    self.pending_changes.remove(pos);
+                    return None;
+                }
+                let before = entry
+                    .before
+                    .iter()
+                    .map(|(_, task)| task)
+                    .find(|task| task.id == change.task_id())?;
+                let compensation = change.inverse(before);
+                self.pending_changes.push(compensation.clone());
+                Some(compensation)
+            })
+            .collect();
+
+        for (index, task) in &entry.before {
+            self.restore_task(*index, task.clone());
+        }
+        self.toasts
+            .push(Severity::Info, format!("Undone: {}", entry.description));
+        if let Some((_, task)) = entry.before.first() {
+            self.focus_task(&task.id);
+        }
+        self.redo_stack.push(entry);
+    }
+
+    /// Reapplies the most recently undone task mutation.
+    pub fn redo(&mut self) {
+        let Some(mut entry) = self.redo_stack.pop() else {
+            self.toasts.push(Severity::Warning, "Nothing to redo");
+            return;
+        };
+
+        // A compensation still in the queue is dropped, otherwise the
+        // original change is sent again
+        for (change, compensation) in entry.changes.iter().zip(&entry.compensations) {
+            let queued = compensation.as_ref().and_then(|compensation| {
+                self.pending_changes
+                    .iter()
+                    .position(|queued| queued == compensation)
             });
-            self.tasks.remove(task_pos.unwrap());
-            if task_pos.unwrap() < self.tasks.len() {
-                self.selected_task = self.tasks.get(task_pos.unwrap()).map(|task| task.id.clone());
-            } else {
-                self.selected_task = self.tasks.get(0).map(|task| task.id.clone());
+            match queued {
+                Some(pos) => {
+                    self.pending_changes.remove(pos);
+                }
+                None => self.pending_changes.push(change.clone()),
+            }
+        }
+        entry.compensations.clear();
+
+        for (index, before) in &entry.before {
+            match entry.after.iter().find(|task| task.id == before.id) {
+                Some(task) => self.restore_task(*index, task.clone()),
+                None => self.tasks.retain(|task| task.id != before.id),
             }
-            Ok(())
-        } else {
-            Err(AppError::TaskNotFound(task_id.clone()))?
         }
+        self.toasts
+            .push(Severity::Info, format!("Redone: {}", entry.description));
+        self.sync_selection();
+        self.history.push(entry);
     }
 
-    pub fn previous(&mut self) {
-        if self.list_state.selected().is_some() {
-            let i = match self.list_state.selected() {
-                Some(i) => {
-                    if i == 0 {
-                        self.tasks.len().saturating_sub(1)
-                    } else {
-                        i - 1
-                    }
+    /// Puts a task snapshot back, at its old position if it is gone.
+    fn restore_task(&mut self, index: usize, snapshot: Task) {
+        match self.tasks.iter_mut().find(|task| task.id == snapshot.id) {
+            Some(task) => *task = snapshot,
+            None => {
+                let index = index.min(self.tasks.len());
+                self.tasks.insert(index, snapshot);
+            }
+        }
+    }
+
+    /// Opens a prompt for the marked tasks or the selected one. Editing
+    /// always applies to the selected task and starts from its content.
+    pub fn open_prompt(&mut self, kind: PromptKind) {
+        let (task_ids, input) = match kind {
+            PromptKind::EditContent => {
+                match self.selected_task.as_deref().map(|id| self.find_task(id)) {
+                    Some(Ok(task)) => (vec![task.id.clone()], task.content.clone()),
+                    _ => return,
+                }
+            }
+            PromptKind::SwitchProfile => {
+                self.prompt = Some(Prompt::new(kind, Vec::new(), String::new()));
+                return;
+            }
+            _ => (self.target_task_ids(), String::new()),
+        };
+        if !task_ids.is_empty() {
+            self.prompt = Some(Prompt::new(kind, task_ids, input));
+        }
+    }
+
+    /// Applies the prompt input to its tasks and closes the prompt.
+    pub fn submit_prompt(&mut self) {
+        let Some(prompt) = self.prompt.take() else {
+            return;
+        };
+        let input = prompt.input.value().trim();
+        let result = match prompt.kind {
+            PromptKind::EditContent if input.is_empty() => {
+                self.toasts
+                    .push(Severity::Warning, "Task content cannot be empty");
+                return;
+            }
+            PromptKind::EditContent => match prompt.task_ids.first() {
+                Some(task_id) => self.edit_task(task_id.clone(), input.to_string()),
+                None => Ok(()),
+            },
+            PromptKind::Reschedule => {
+                let due_string = if input.is_empty() { "no date" } else { input };
+                self.reschedule_tasks(&prompt.task_ids, due_string)
+            }
+            PromptKind::MoveToProject => match match_project(input, &self.projects).cloned() {
+                Some(project) => self.move_tasks(&prompt.task_ids, &project),
+                None => {
+                    self.toasts
+                        .push(Severity::Error, format!("No project matches “{}”", input));
+                    return;
                 }
-                None => 0,
-            };
-            self.list_state.select(Some(i));
-            self.selected_task = Some(self.tasks[i].id.clone());
+            },
+            PromptKind::SetPriority => match parse_priority(input) {
+                Some(priority) => self.set_priority(&prompt.task_ids, priority),
+                None => {
+                    self.toasts
+                        .push(Severity::Error, "Priority must be between 1 and 4");
+                    return;
+                }
+            },
+            PromptKind::AddLabel => match input.trim_start_matches('@') {
+                "" => return,
+                label => self.add_label(&prompt.task_ids, label),
+            },
+            PromptKind::SwitchProfile if input.is_empty() => return,
+            PromptKind::SwitchProfile => self.switch_profile(input).map_err(Into::into),
+        };
+        if let Err(e) = result {
+            self.toasts.push(Severity::Error, e.to_string());
+        }
+    }
+
+    /// Opens the month view on the selected task's due day, or on today.
+    pub fn open_calendar(&mut self) {
+        let date = self
+            .selected_task
+            .as_deref()
+            .and_then(|id| self.find_task(id).ok())
+            .and_then(calendar::due_date)
+            .unwrap_or_else(|| chrono::Local::now().date_naive());
+        self.calendar = Some(Calendar::new(date, self.settings.week_start.weekday()));
+    }
+
+    /// Opens the day and week timeline on today.
+    pub fn open_agenda(&mut self) {
+        self.agenda = Some(Agenda::new(
+            chrono::Local::now().naive_local(),
+            self.settings.week_start.weekday(),
+        ));
+    }
+
+    /// Open tasks due on the day selected in the month view.
+    pub fn calendar_tasks(&self) -> Vec<&Task> {
+        match &self.calendar {
+            Some(calendar) => calendar::tasks_on(&self.tasks, calendar.date),
+            None => Vec::new(),
+        }
+    }
+
+    /// Picks up the task selected in the month view, or drops the picked up
+    /// task on the selected day.
+    pub fn calendar_submit(&mut self) {
+        let Some(calendar) = &mut self.calendar else {
+            return;
+        };
+        match calendar.moving.take() {
+            Some(task_id) => {
+                let date = calendar.date;
+                if let Err(e) = self.move_to_day(&task_id, date) {
+                    self.toasts.push(Severity::Error, e.to_string());
+                }
+                let index = self
+                    .calendar_tasks()
+                    .iter()
+                    .position(|task| task.id == task_id);
+                if let Some(calendar) = &mut self.calendar {
+                    calendar.list_state.select(index.or(Some(0)));
+                }
+            }
+            None => {
+                let selected = calendar.list_state.selected().unwrap_or(0);
+                let task_id = self
+                    .calendar_tasks()
+                    .get(selected)
+                    .map(|task| task.id.clone());
+                if let Some(calendar) = &mut self.calendar {
+                    calendar.moving = task_id;
+                }
+            }
+        }
+    }
+
+    /// Moves the selection in the month view's task list, without wrapping.
+    pub fn calendar_select(&mut self, offset: isize) {
+        let count = self.calendar_tasks().len();
+        if let Some(calendar) = &mut self.calendar {
+            let selected = calendar.list_state.selected().unwrap_or(0);
+            let index = selected.saturating_add_signed(offset);
+            calendar
+                .list_state
+                .select(Some(index.min(count.saturating_sub(1))));
+        }
+    }
+
+    /// Tasks a task action applies to: the marked ones if any, otherwise the
+    /// selected task.
+    pub fn target_task_ids(&self) -> Vec<String> {
+        let marked = self.marked_task_ids();
+        if marked.is_empty() {
+            self.selected_task.iter().cloned().collect()
         } else {
-            let today_tasks_count = self
-                .tasks
-                .iter()
-                .filter(|task| {
-                    if let Some(due) = &task.due {
-                        let today = chrono::Local::now().date_naive();
-                        let task_date =
-                            chrono::NaiveDate::parse_from_str(&due.date, "%Y-%m-%d").ok();
-                        task_date == Some(today)
-                    } else {
-                        false
-                    }
-                })
-                .count();
-
-            let i = match self.today_list_state.selected() {
-                Some(i) => {
-                    if i == 0 {
-                        today_tasks_count.saturating_sub(1)
-                    } else {
-                        i - 1
-                    }
+            marked
+        }
+    }
+
+    /// The tasks a count such as `5dd` applies to: the selected task and
+    /// the ones below it, `count` in all. Marked tasks take precedence.
+    pub fn counted_task_ids(&self, count: usize) -> Vec<String> {
+        let marked = self.marked_task_ids();
+        if !marked.is_empty() {
+            return marked;
+        }
+        let Some(index) = self.selected_index() else {
+            return Vec::new();
+        };
+        self.focused_tasks()
+            .iter()
+            .skip(index)
+            .take(count)
+            .map(|task| task.id.clone())
+            .collect()
+    }
+
+    /// Marked tasks including the range being selected.
+    pub fn marked_task_ids(&self) -> Vec<String> {
+        let mut ids = self.marked.clone();
+        if let (Some(anchor), Some(cursor)) = (self.visual_anchor, self.selected_index()) {
+            let range = anchor.min(cursor)..=anchor.max(cursor);
+            for task in self.focused_tasks().get(range).unwrap_or_default() {
+                if !ids.contains(&task.id) {
+                    ids.push(task.id.clone());
                 }
-                None => 0,
-            };
-            self.today_list_state.select(Some(i));
-            let selected_task_id = self.today_tasks().get(i).unwrap().id.clone();
-            self.selected_task = self
-                .tasks
-                .iter()
-                .filter(|task| task.id == selected_task_id)
-                .map(|task| task.id.clone())
-                .next();
+            }
+        }
+        ids
+    }
+
+    /// Marks or unmarks the selected task and moves on to the next one.
+    pub fn toggle_mark(&mut self) {
+        let (Some(task_id), Some(index)) = (self.selected_task.clone(), self.selected_index())
+        else {
+            return;
+        };
+        match self.marked.iter().position(|id| *id == task_id) {
+            Some(pos) => {
+                self.marked.remove(pos);
+            }
+            None => self.marked.push(task_id),
+        }
+        self.select_index(index + 1);
+    }
+
+    /// Starts a range selection at the cursor, or ends it keeping the range
+    /// marked.
+    pub fn toggle_range(&mut self) {
+        if self.visual_anchor.is_some() {
+            self.end_range();
+        } else {
+            self.visual_anchor = self.selected_index();
+        }
+    }
+
+    /// Ends a range selection, keeping the range marked.
+    pub fn end_range(&mut self) {
+        self.marked = self.marked_task_ids();
+        self.visual_anchor = None;
+    }
+
+    /// Marks every task in the focused list.
+    pub fn mark_all(&mut self) {
+        self.visual_anchor = None;
+        let ids: Vec<String> = self
+            .focused_tasks()
+            .iter()
+            .map(|task| task.id.clone())
+            .collect();
+        for id in ids {
+            if !self.marked.contains(&id) {
+                self.marked.push(id);
+            }
+        }
+    }
+
+    pub fn clear_marks(&mut self) {
+        self.marked.clear();
+        self.visual_anchor = None;
+    }
+
+    /// Index of the cursor in the focused list.
+    pub fn selected_index(&self) -> Option<usize> {
+        self.pane_state(self.focused).selected()
+    }
+
+    /// Open tasks shown in a pane, in display order.
+    pub fn pane_tasks(&self, pane: Pane) -> Vec<&Task> {
+        match pane {
+            Pane::Today => self.today_tasks(),
+            Pane::Inbox => self.inbox_tasks(),
+        }
+    }
+
+    pub fn focused_tasks(&self) -> Vec<&Task> {
+        self.pane_tasks(self.focused)
+    }
+
+    fn pane_state(&self, pane: Pane) -> &ListState {
+        match pane {
+            Pane::Today => &self.today_list_state,
+            Pane::Inbox => &self.list_state,
+        }
+    }
+
+    fn pane_state_mut(&mut self, pane: Pane) -> &mut ListState {
+        match pane {
+            Pane::Today => &mut self.today_list_state,
+            Pane::Inbox => &mut self.list_state,
         }
     }
+
+    /// Selects the task at `index` in the focused list, clamped to its end.
+    pub fn select_index(&mut self, index: usize) {
+        let tasks = self.focused_tasks();
+        let index = index.min(tasks.len().saturating_sub(1));
+        self.selected_task = tasks.get(index).map(|task| task.id.clone());
+        self.sync_selection();
+    }
+
+    /// Rows the focused list showed in the last frame.
+    fn page_size(&self) -> usize {
+        let pane = self.focused_pane();
+        self.rendered
+            .lists
+            .iter()
+            .find(|list| list.pane == pane)
+            .map_or(10, |list| list.rows.height.max(1) as usize)
+    }
+
+    /// Moves the cursor and the view of the focused list a page down, or up
+    /// for negative `pages`, stopping at either end.
+    pub fn page(&mut self, pages: isize) {
+        let Some(index) = self.selected_index() else {
+            return self.select_first();
+        };
+        let page = self.page_size();
+        let delta = pages * page as isize;
+        let last_page = self.focused_tasks().len().saturating_sub(page);
+        let state = self.pane_state_mut(self.focused);
+        *state.offset_mut() = state.offset().saturating_add_signed(delta).min(last_page);
+        self.select_index(index.saturating_add_signed(delta));
+    }
+
+    pub fn select_first(&mut self) {
+        self.select_index(0);
+    }
+
+    pub fn select_last(&mut self) {
+        self.select_index(usize::MAX);
+    }
+
+    /// Points the list states at the selected task after the lists changed,
+    /// so the cursor stays on the same task rather than the same row. When
+    /// the task left the list, the cursor stays on its row instead, within
+    /// the bounds of the list.
+    fn sync_selection(&mut self) {
+        let tasks = self.focused_tasks();
+        let index = self
+            .selected_task
+            .as_deref()
+            .and_then(|id| tasks.iter().position(|task| task.id == id))
+            .or_else(|| {
+                let row = self.selected_index().unwrap_or(0);
+                (!tasks.is_empty()).then(|| row.min(tasks.len() - 1))
+            });
+        self.selected_task = index.map(|i| tasks[i].id.clone());
+
+        let (focused, other) = (self.focused, self.focused.other());
+        let state = self.pane_state_mut(focused);
+        match index {
+            Some(i) => state.select(Some(i)),
+            None => deselect(state),
+        }
+        deselect(self.pane_state_mut(other));
+    }
+
+    /// Selects the previous task of the focused list, wrapping around.
+    pub fn previous(&mut self) {
+        match self.selected_index() {
+            Some(i) if i > 0 => self.select_index(i - 1),
+            _ => self.select_last(),
+        }
+    }
+}
+
+/// Clears a list's selection but keeps it scrolled where it was.
+fn deselect(state: &mut ListState) {
+    let offset = state.offset();
+    state.select(None);
+    *state.offset_mut() = offset;
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::todoist::test_task;
+    use std::fs;
+
+    /// An app for a new profile in a temporary directory.
+    fn test_app() -> App {
+        let base = std::env::temp_dir().join(format!("todoclist-{}", uuid::Uuid::new_v4()));
+        let profile = Profile::in_dirs("default", &base.join("config"), &base.join("cache"));
+        App::new(profile.unwrap())
+    }
+
+    /// An open task without due date, labels or project.
+    fn task(id: &str) -> Task {
+        test_task(id, None, None)
+    }
+
+    #[test]
+    fn test_rejected_key_can_be_reentered() {
+        let mut app = App {
+            input_buffer: TextInput::new("typo"),
+            ..test_app()
+        };
+        let (tx, result) = oneshot::channel();
+        app.key_check = Some(KeyCheck {
+            api_key: "typo".to_string(),
+            started: Instant::now(),
+            result,
+        });
+
+        // Still waiting for Todoist
+        app.poll_key_check();
+        assert!(app.key_check.is_some());
+
+        tx.send(Err(KeyError::Rejected)).unwrap();
+        app.poll_key_check();
+        assert!(app.key_check.is_none());
+        assert_eq!(app.key_error, Some(KeyError::Rejected));
+        assert!(!app.onboarding_complete);
+        assert_eq!(app.input_buffer.value(), "typo");
+    }
+
+    #[test]
+    fn test_quit_asks_before_discarding_unsent_changes() {
+        let mut app = test_app();
+        app.request_quit();
+        assert!(!app.running);
+
+        let mut app = App {
+            pending_changes: vec![TaskCompletion {
+                task_id: "1".to_string(),
+                completed: true,
+            }],
+            ..test_app()
+        };
+        app.request_quit();
+        assert!(app.running);
+        assert!(app
+            .toasts
+            .iter()
+            .next()
+            .unwrap()
+            .message
+            .starts_with("1 unsent"));
+        app.request_quit();
+        assert!(!app.running);
+    }
+
+    #[test]
+    fn test_quit_and_logout_wait_for_the_sync() {
+        let change = TaskCompletion {
+            task_id: "1".to_string(),
+            completed: true,
+        };
+        let mut app = App {
+            pending_changes: vec![change.clone()],
+            todoist_client: Some(TodoistClient::new("secret".to_string())),
+            ..test_app()
+        };
+
+        // The background sync takes the changes and reports them sent
+        app.flush_then(Action::Quit);
+        assert!(app.running);
+        app.pending_changes.clear();
+        app.apply_sync_message(SyncMessage::Sent { count: 1 });
+        assert!(!app.running);
+
+        app.running = true;
+        app.pending_changes.push(change);
+        app.flush_then(Action::Logout);
+        let changes = std::mem::take(&mut app.pending_changes);
+        app.apply_sync_message(SyncMessage::Unsent { changes });
+        app.apply_sync_message(SyncMessage::Failed {
+            error: "offline".to_string(),
+            offline: true,
+        });
+        assert_eq!(app.after_flush, None);
+        assert_eq!(app.pending_changes.len(), 1);
+        assert!(app.todoist_client.is_some());
+        assert!(app
+            .toasts
+            .iter()
+            .any(|toast| toast.message.starts_with("Logout failed")));
+    }
+
+    #[test]
+    fn test_logout_keeps_unsent_changes() {
+        let mut app = test_app();
+        app.api_key_manager
+            .save_api_key("todoist", "secret")
+            .unwrap();
+        app.pending_changes.push(TaskCompletion {
+            task_id: "1".to_string(),
+            completed: true,
+        });
+        assert!(app.logout().is_err());
+        assert_eq!(app.pending_changes.len(), 1);
+        assert!(app.api_key_manager.load_api_key("todoist").is_ok());
+
+        app.pending_changes.clear();
+        app.logout().unwrap();
+        assert!(app.api_key_manager.load_api_key("todoist").is_err());
+        assert!(!app.onboarding_complete);
+    }
+
+    #[test]
+    fn test_settings_reload() {
+        let mut app = test_app();
+        app.load_settings().unwrap();
+        assert_eq!(app.theme.name, "dark");
+
+        // Edits are picked up by their modification time
+        let path = app.profile.settings_path();
+        let write = |text: &str, seconds: u64| {
+            fs::write(&path, text).unwrap();
+            let time = SystemTime::now() + Duration::from_secs(seconds);
+            let file = fs::File::options().write(true).open(&path).unwrap();
+            file.set_modified(time).unwrap();
+        };
+        write("theme = \"light\"\nrefresh_interval = 60\n", 10);
+        app.reload_settings();
+        assert_eq!(app.theme.name, "light");
+        assert_eq!(app.refresh_interval, 60);
+
+        // An invalid file is reported once and changes nothing
+        write(
+            "theme = \"light\"\n[keymap]\nno_such_action = [\"z\"]\n",
+            20,
+        );
+        app.reload_settings();
+        app.reload_settings();
+        assert_eq!(app.refresh_interval, 60);
+        let messages: Vec<&str> = app
+            .toasts
+            .iter()
+            .map(|toast| toast.message.as_str())
+            .collect();
+        assert_eq!(messages.len(), 2);
+        assert!(messages[1].contains("no_such_action"), "{}", messages[1]);
+
+        // Switching themes in the app does not count as an edit
+        app.set_theme("solarized").unwrap();
+        app.reload_settings();
+        assert_eq!(app.toasts.iter().count(), 3);
+    }
 
     #[tokio::test]
     async fn test_toggle_task_completion() {
         let mut app = App {
-            tasks: vec![Task {
-                id: "1".to_string(),
-                content: "Test task".to_string(),
-                description: "".to_string(),
-                is_completed: false,
-                labels: vec![],
-                due: None,
-            }],
-            ..Default::default()
+            tasks: vec![task("1")],
+            ..test_app()
         };
 
         // Initial state
@@ -296,4 +1701,299 @@ mod tests {
         // Test error case
         assert!(app.toggle_task_completion("999".to_string()).await.is_err());
     }
+
+    #[tokio::test]
+    async fn test_delete_and_undo() {
+        let mut app = App {
+            tasks: vec![task("1"), task("2")],
+            ..test_app()
+        };
+
+        app.delete_task("1".to_string()).unwrap();
+        assert_eq!(app.tasks.len(), 1);
+        assert_eq!(app.pending_changes.len(), 1);
+
+        // Undoing a queued deletion drops it instead of syncing anything
+        app.undo();
+        assert_eq!(app.tasks[0].id, "1");
+        assert!(app.pending_changes.is_empty());
+
+        // A completion that was already sent is reverted by reopening
+        app.toggle_task_completion("2".to_string()).await.unwrap();
+        app.pending_changes.clear();
+        app.undo();
+        assert!(!app.tasks[1].is_completed);
+        assert_eq!(
+            app.pending_changes,
+            vec![TaskCompletion {
+                task_id: "2".to_string(),
+                completed: false
+            }]
+        );
+    }
+
+    #[test]
+    fn test_redo_and_compensating_changes() {
+        let task = |id: &str| Task {
+            project_id: "inbox".to_string(),
+            ..task(id)
+        };
+        let mut app = App {
+            tasks: vec![task("1"), task("2")],
+            ..test_app()
+        };
+
+        // Redo sends a change again once its undo was dropped from the queue
+        app.edit_task("1".to_string(), "Renamed".to_string())
+            .unwrap();
+        app.undo();
+        assert_eq!(app.tasks[0].content, "Task 1");
+        assert!(app.pending_changes.is_empty());
+        app.redo();
+        assert_eq!(app.tasks[0].content, "Renamed");
+        assert_eq!(app.pending_changes.len(), 1);
+
+        // A sent deletion is undone by recreating the task, and redoing
+        // while the recreation is still queued just drops it
+        app.pending_changes.clear();
+        app.delete_task("2".to_string()).unwrap();
+        app.pending_changes.clear();
+        app.undo();
+        assert_eq!(app.tasks.len(), 2);
+        assert!(matches!(
+            app.pending_changes[..],
+            [PendingChange::TaskCreation { .. }]
+        ));
+        app.redo();
+        assert_eq!(app.tasks.len(), 1);
+        assert!(app.pending_changes.is_empty());
+
+        // Once recreated, the new id is used for the next deletion
+        app.undo();
+        app.pending_changes.clear();
+        app.apply_sync_message(SyncMessage::Created {
+            old_id: "2".to_string(),
+            task: Box::new(task("20")),
+        });
+        assert!(app.tasks.iter().any(|task| task.id == "20"));
+        app.redo();
+        assert_eq!(
+            app.pending_changes,
+            vec![TaskDeletion {
+                task_id: "20".to_string()
+            }]
+        );
+
+        // A new mutation clears the redo stack
+        app.undo();
+        app.move_tasks(
+            &["1".to_string()],
+            &Project {
+                id: "work".to_string(),
+                name: "Work".to_string(),
+            },
+        )
+        .unwrap();
+        assert_eq!(app.tasks[0].project_id, "work");
+        assert!(app.redo_stack.is_empty());
+    }
+
+    #[test]
+    fn test_bulk_operations() {
+        let task = |id: &str| Task {
+            labels: vec!["home".to_string()],
+            ..task(id)
+        };
+        let mut app = App {
+            tasks: vec![task("1"), task("2"), task("3"), task("4")],
+            ..test_app()
+        };
+        app.focus_pane(Pane::Inbox);
+
+        // Mark the first task, then a range from the third to the last
+        app.toggle_mark();
+        app.select_index(2);
+        app.toggle_range();
+        app.select_last();
+        assert_eq!(app.target_task_ids(), vec!["1", "3", "4"]);
+        app.toggle_range();
+        assert_eq!(app.marked, vec!["1", "3", "4"]);
+
+        // One bulk action is one undo step and clears the marks
+        app.toggle_tasks_completion(&app.target_task_ids()).unwrap();
+        assert_eq!(app.inbox_tasks().len(), 1);
+        assert_eq!(app.pending_changes.len(), 3);
+        assert!(app.marked.is_empty());
+        app.undo();
+        assert_eq!(app.inbox_tasks().len(), 4);
+        assert!(app.pending_changes.is_empty());
+
+        // Tasks that already have the label are left alone
+        app.mark_all();
+        app.add_label(&app.target_task_ids(), "home").unwrap();
+        assert!(app.pending_changes.is_empty());
+        app.set_priority(&["2".to_string()], 4).unwrap();
+        assert_eq!(app.tasks[1].priority, 4);
+        app.clear_marks();
+
+        // A count covers the selected task and the ones below it
+        app.select_index(1);
+        assert_eq!(app.counted_task_ids(2), vec!["2", "3"]);
+        assert_eq!(app.counted_task_ids(9999), vec!["2", "3", "4"]);
+        let steps = app.history.len();
+        app.delete_tasks(&app.counted_task_ids(9999)).unwrap();
+        assert_eq!(app.inbox_tasks().len(), 1);
+        assert_eq!(app.history.len(), steps + 1);
+    }
+
+    #[test]
+    fn test_apply_sync_message() {
+        let mut app = App {
+            pending_changes: vec![TaskDeletion {
+                task_id: "1".to_string(),
+            }],
+            ..test_app()
+        };
+
+        // Queued changes are replayed on top of freshly fetched tasks
+        app.apply_sync_message(SyncMessage::Refreshed {
+            tasks: vec![task("1"), task("2")],
+            projects: None,
+        });
+        assert_eq!(app.tasks.len(), 1);
+        assert_eq!(app.sync_status.online, Some(true));
+
+        // Unsent changes go back to the front of the queue
+        app.apply_sync_message(SyncMessage::Unsent {
+            changes: vec![TaskCompletion {
+                task_id: "2".to_string(),
+                completed: true,
+            }],
+        });
+        assert_eq!(app.pending_changes.len(), 2);
+        assert!(matches!(app.pending_changes[0], TaskCompletion { .. }));
+
+        app.apply_sync_message(SyncMessage::Failed {
+            error: "timed out".to_string(),
+            offline: true,
+        });
+        assert_eq!(app.sync_status.online, Some(false));
+        assert!(app.sync_status.last_error.is_some());
+    }
+
+    #[test]
+    fn test_paging_keeps_scroll_offsets() {
+        let mut app = App {
+            tasks: (0..25).map(|i| task(&i.to_string())).collect(),
+            ..test_app()
+        };
+        app.rendered.lists.push(crate::layout::ListArea {
+            pane: Pane::Inbox,
+            area: ratatui::layout::Rect::new(0, 0, 40, 12),
+            rows: ratatui::layout::Rect::new(1, 1, 38, 10),
+            offset: 0,
+            checkbox_x: 6,
+        });
+        app.focus_pane(Pane::Inbox);
+
+        app.page(1);
+        assert_eq!(app.selected_index(), Some(10));
+        assert_eq!(app.list_state.offset(), 10);
+
+        // The last page stays full and the cursor stops at the end
+        app.page(2);
+        assert_eq!(app.selected_index(), Some(24));
+        assert_eq!(app.list_state.offset(), 15);
+
+        // Leaving the pane and refreshing keep where it was scrolled to
+        app.focus_pane(Pane::Today);
+        app.apply_sync_message(SyncMessage::Refreshed {
+            tasks: (0..25).map(|i| task(&i.to_string())).collect(),
+            projects: None,
+        });
+        assert_eq!(app.list_state.offset(), 15);
+
+        // Coming back returns to the task the cursor was on
+        app.focus_pane(Pane::Inbox);
+        assert_eq!(app.selected_task.as_deref(), Some("24"));
+        app.page(-1);
+        assert_eq!(app.selected_index(), Some(14));
+        assert_eq!(app.list_state.offset(), 5);
+    }
+
+    #[test]
+    fn test_selection_follows_task_across_refreshes() {
+        let mut app = App {
+            tasks: vec![task("1"), task("2"), task("3")],
+            ..test_app()
+        };
+
+        // Today is empty: moving around does nothing instead of panicking
+        app.next();
+        app.previous();
+        assert_eq!(app.selected_task, None);
+
+        app.focus_pane(Pane::Inbox);
+        app.select_index(1);
+
+        // A new task above the cursor keeps it on the same task
+        app.apply_sync_message(SyncMessage::Refreshed {
+            tasks: vec![task("0"), task("1"), task("2"), task("3")],
+            projects: None,
+        });
+        assert_eq!(app.selected_task.as_deref(), Some("2"));
+        assert_eq!(app.selected_index(), Some(2));
+
+        // When the task goes away the cursor stays on its row, in bounds
+        app.apply_sync_message(SyncMessage::Refreshed {
+            tasks: vec![task("0"), task("1")],
+            projects: None,
+        });
+        assert_eq!(app.selected_task.as_deref(), Some("1"));
+        assert_eq!(app.selected_index(), Some(1));
+
+        app.apply_sync_message(SyncMessage::Refreshed {
+            tasks: vec![],
+            projects: None,
+        });
+        assert_eq!(app.selected_task, None);
+        assert_eq!(app.selected_index(), None);
+    }
+
+    #[test]
+    fn test_calendar_move_keeps_time_of_day() {
+        let mut app = App {
+            tasks: vec![
+                test_task("1", Some("2024-03-04T09:30:00"), Some(30)),
+                test_task("2", Some("2024-03-04"), None),
+            ],
+            ..test_app()
+        };
+        let date = NaiveDate::from_ymd_opt(2024, 3, 6).unwrap();
+        for task_id in ["1", "2"] {
+            app.calendar = Some(Calendar::new(date, chrono::Weekday::Mon));
+            app.calendar.as_mut().unwrap().moving = Some(task_id.to_string());
+            app.calendar_submit();
+        }
+
+        assert_eq!(
+            agenda::due_datetime(&app.tasks[0]),
+            date.and_hms_opt(9, 30, 0)
+        );
+        assert_eq!(app.tasks[0].due.as_ref().unwrap().date, "2024-03-06");
+        assert_eq!(app.tasks[1].due.as_ref().unwrap().date, "2024-03-06");
+        assert_eq!(app.tasks[1].due.as_ref().unwrap().datetime, None);
+
+        let updates: Vec<&TaskUpdate> = app
+            .pending_changes
+            .iter()
+            .filter_map(|change| match change {
+                PendingChange::TaskUpdate { update, .. } => Some(update),
+                _ => None,
+            })
+            .collect();
+        assert!(updates[0].due_datetime.as_deref().unwrap().ends_with('Z'));
+        assert_eq!(updates[0].due_date, None);
+        assert_eq!(updates[1].due_date.as_deref(), Some("2024-03-06"));
+    }
 }
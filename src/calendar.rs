@@ -0,0 +1,159 @@
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+use ratatui::widgets::ListState;
+use std::collections::HashMap;
+
+use crate::todoist::Task;
+
+/// State of the month view: the selected day, the task selected among those
+/// due that day and the task being moved to another day, if any.
+#[derive(Debug)]
+pub struct Calendar {
+    pub date: NaiveDate,
+    pub list_state: ListState,
+    /// Task picked up with enter, dropped on the selected day with enter.
+    pub moving: Option<String>,
+    pub week_start: Weekday,
+}
+
+/// Tasks due on a day and the most urgent priority among them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DaySummary {
+    pub count: usize,
+    pub priority: u8,
+}
+
+impl Calendar {
+    pub fn new(date: NaiveDate, week_start: Weekday) -> Self {
+        Self {
+            date,
+            list_state: ListState::default().with_selected(Some(0)),
+            moving: None,
+            week_start,
+        }
+    }
+
+    pub fn move_days(&mut self, days: i64) {
+        let date = if days < 0 {
+            self.date.checked_sub_days(Days::new(days.unsigned_abs()))
+        } else {
+            self.date.checked_add_days(Days::new(days as u64))
+        };
+        self.select_date(date.unwrap_or(self.date));
+    }
+
+    /// Moves by whole months, keeping the day where the month has it and
+    /// using the last day otherwise.
+    pub fn move_months(&mut self, months: i32) {
+        let date = if months < 0 {
+            self.date
+                .checked_sub_months(Months::new(months.unsigned_abs()))
+        } else {
+            self.date.checked_add_months(Months::new(months as u32))
+        };
+        self.select_date(date.unwrap_or(self.date));
+    }
+
+    pub fn select_date(&mut self, date: NaiveDate) {
+        self.date = date;
+        self.list_state.select(Some(0));
+    }
+
+    /// Weeks from `week_start` on covering the month of the selected day.
+    pub fn weeks(&self) -> Vec<[NaiveDate; 7]> {
+        let first = self.date.with_day(1).unwrap_or(self.date);
+        let mut start = week_of(first, self.week_start);
+        let mut weeks = Vec::new();
+        while start.month() == first.month() || start <= first {
+            weeks.push(std::array::from_fn(|i| start + Days::new(i as u64)));
+            start = start + Days::new(7);
+        }
+        weeks
+    }
+}
+
+/// The first day of the week `date` is in, for weeks starting on
+/// `week_start`.
+pub fn week_of(date: NaiveDate, week_start: Weekday) -> NaiveDate {
+    let days = (7 + date.weekday().num_days_from_monday() - week_start.num_days_from_monday()) % 7;
+    date - Days::new(days as u64)
+}
+
+/// The day a task is due, ignoring any time of day.
+pub fn due_date(task: &Task) -> Option<NaiveDate> {
+    let due = task.due.as_ref()?;
+    NaiveDate::parse_from_str(due.date.get(..10)?, "%Y-%m-%d").ok()
+}
+
+/// Open tasks due on `date`, most urgent first.
+pub fn tasks_on(tasks: &[Task], date: NaiveDate) -> Vec<&Task> {
+    let mut due: Vec<&Task> = tasks
+        .iter()
+        .filter(|task| !task.is_completed && due_date(task) == Some(date))
+        .collect();
+    due.sort_by_key(|task| std::cmp::Reverse(task.priority));
+    due
+}
+
+/// Open tasks per due day.
+pub fn day_summaries(tasks: &[Task]) -> HashMap<NaiveDate, DaySummary> {
+    let mut summaries: HashMap<NaiveDate, DaySummary> = HashMap::new();
+    for task in tasks.iter().filter(|task| !task.is_completed) {
+        if let Some(date) = due_date(task) {
+            let summary = summaries.entry(date).or_insert(DaySummary {
+                count: 0,
+                priority: 1,
+            });
+            summary.count += 1;
+            summary.priority = summary.priority.max(task.priority);
+        }
+    }
+    summaries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::todoist::test_task;
+
+    #[test]
+    fn test_month_navigation_and_summaries() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let mut calendar = Calendar::new(date(2024, 1, 31), Weekday::Mon);
+
+        // February 2024 starts on a Thursday and ends on a Thursday
+        calendar.move_months(1);
+        assert_eq!(calendar.date, date(2024, 2, 29));
+        let weeks = calendar.weeks();
+        assert_eq!(weeks.len(), 5);
+        assert_eq!(weeks[0][0], date(2024, 1, 29));
+        assert_eq!(weeks[4][6], date(2024, 3, 3));
+        calendar.week_start = Weekday::Sun;
+        assert_eq!(calendar.weeks()[0][0], date(2024, 1, 28));
+
+        calendar.move_days(-7);
+        assert_eq!(calendar.date, date(2024, 2, 22));
+
+        let task = |id: &str, due: &str, priority| Task {
+            priority,
+            ..test_task(id, Some(due), None)
+        };
+        let tasks = vec![
+            task("1", "2024-02-22", 2),
+            task("2", "2024-02-22T09:00:00", 4),
+            task("3", "2024-02-23", 1),
+        ];
+        let summaries = day_summaries(&tasks);
+        assert_eq!(
+            summaries[&date(2024, 2, 22)],
+            DaySummary {
+                count: 2,
+                priority: 4
+            }
+        );
+        let due: Vec<&str> = tasks_on(&tasks, calendar.date)
+            .iter()
+            .map(|task| task.id.as_str())
+            .collect();
+        assert_eq!(due, vec!["2", "1"]);
+    }
+}
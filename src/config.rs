@@ -1,12 +1,57 @@
 use aes_gcm::{
-    aead::{Aead, KeyInit},
-    Aes256Gcm, Nonce
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce
 };
+use argon2::{Algorithm, Argon2, Params, Version};
 use base64::{engine::general_purpose, Engine as _};
-use directories::ProjectDirs;
+use crate::settings::{Settings, MIN_REFRESH_INTERVAL, SETTINGS_FILE};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Format of `config.json`, one more than the number of migrations.
+const CONFIG_VERSION: u8 = MIGRATIONS.len() as u8 + 1;
+
+/// A step upgrading `config.json` from one version to the next, applied to
+/// the parsed JSON since older formats do not fit [`Config`].
+struct Migration {
+    from: u8,
+    apply: fn(&ApiKeyManager, &mut Map<String, Value>) -> Result<(), String>,
+}
+
+/// Every migration in order, starting from version 1.
+const MIGRATIONS: &[Migration] = &[
+    // Version 1 encrypted every key with the same built-in key and nonce;
+    // version 2 uses the install's random data key and a nonce per key
+    Migration {
+        from: 1,
+        apply: migrate_keys,
+    },
+    // Version 3 moved the settings to settings.toml
+    Migration {
+        from: 2,
+        apply: migrate_settings,
+    },
+];
+
+/// Settings kept in `config.json` before version 3.
+const LEGACY_SETTINGS: &[&str] = &[
+    "refresh_interval",
+    "keymap",
+    "vim_mode",
+    "theme",
+    "themes",
+    "layout",
+];
+
+/// Key and nonce used by version 1 configs, only kept to migrate them.
+const LEGACY_KEY: &[u8; 32] = b"0123456789abcdef0123456789abcdef";
+const LEGACY_NONCE: &[u8; 12] = b"unique nonce";
+
+/// Text encrypted with the vault key to recognise a wrong passphrase.
+const VAULT_CHECK: &str = "todoclist vault";
 
 #[derive(Serialize, Deserialize)]
 struct EncryptedKey {
@@ -17,48 +62,175 @@ struct EncryptedKey {
     last_used: Option<String>, // ISO 8601 timestamp
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct Config {
     keys: Vec<EncryptedKey>,
-    version: u8, // For future schema changes
-    refresh_interval: Option<u64>, // Refresh interval in seconds
+    version: u8, // See CONFIG_VERSION
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    vault: Option<Vault>, // Set when keys are protected by a passphrase
+}
+
+/// Passphrase protection for the API keys. The key encrypting them is
+/// derived with Argon2id from the passphrase, the salt and the parameters
+/// stored here, and never written to disk.
+#[derive(Serialize, Deserialize)]
+struct Vault {
+    salt: String,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+    check: String,
+    check_nonce: String,
+}
+
+impl Vault {
+    /// A vault with a fresh salt and default parameters, and its key.
+    fn create(passphrase: &str) -> Result<(Vault, Key<Aes256Gcm>), String> {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        let params = Params::default();
+        let mut vault = Vault {
+            salt: general_purpose::STANDARD.encode(salt),
+            memory_kib: params.m_cost(),
+            iterations: params.t_cost(),
+            parallelism: params.p_cost(),
+            check: String::new(),
+            check_nonce: String::new(),
+        };
+        let key = vault.derive_key(passphrase)?;
+        (vault.check, vault.check_nonce) = encrypt(&key, VAULT_CHECK)?;
+        Ok((vault, key))
+    }
+
+    fn derive_key(&self, passphrase: &str) -> Result<Key<Aes256Gcm>, String> {
+        let salt = general_purpose::STANDARD.decode(&self.salt)
+            .map_err(|e| format!("Failed to decode vault salt: {}", e))?;
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, Some(32))
+            .map_err(|e| format!("Invalid vault parameters: {}", e))?;
+
+        let mut key = Key::<Aes256Gcm>::default();
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|e| format!("Failed to derive vault key: {}", e))?;
+        Ok(key)
+    }
 }
 
 impl Config {
-    pub fn refresh_interval(&self) -> u64 {
-        self.refresh_interval.unwrap_or(30)
+    fn new() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            ..Self::default()
+        }
     }
 }
 
-#[derive(Debug)]
 pub struct ApiKeyManager {
     config_path: PathBuf,
-    encryption_key: [u8; 32],
+    /// Random data key encrypting the API keys, readable by the owner only.
+    key_path: PathBuf,
+    /// Key derived from the vault passphrase, kept in memory only.
+    unlocked: Option<Key<Aes256Gcm>>,
+}
+
+impl std::fmt::Debug for ApiKeyManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApiKeyManager")
+            .field("config_path", &self.config_path)
+            .field("unlocked", &self.unlocked.is_some())
+            .finish_non_exhaustive()
+    }
 }
 
 impl ApiKeyManager {
-    pub fn new() -> Self {
-        let proj_dirs = ProjectDirs::from("dev", "mmlak", "todoclist")
-            .expect("Could not determine config directory");
-        
-        let config_dir = proj_dirs.config_dir();
-        fs::create_dir_all(config_dir).expect("Failed to create config directory");
-        
+    /// Keeps the config and the data key in `dir`, see
+    /// [`Profile::key_manager`](crate::profile::Profile::key_manager).
+    pub fn in_dir(dir: &Path) -> Self {
         Self {
-            config_path: config_dir.join("config.json"),
-            encryption_key: *b"0123456789abcdef0123456789abcdef", // TODO: Replace with proper key management
+            config_path: dir.join("config.json"),
+            key_path: dir.join("data.key"),
+            unlocked: None,
         }
     }
 
-    pub fn save_refresh_interval(&self, interval: u64) -> Result<(), String> {
-        let mut config = self.load_config().unwrap_or_else(|_| Config {
-            keys: Vec::new(),
-            version: 1,
-            refresh_interval: None,
-        });
+    /// Reads the data key, generating it on first use.
+    fn data_key(&self) -> Result<Key<Aes256Gcm>, String> {
+        match fs::read(&self.key_path) {
+            Ok(bytes) if bytes.len() == 32 => Ok(*Key::<Aes256Gcm>::from_slice(&bytes)),
+            Ok(_) => Err(format!("Invalid data key in {}", self.key_path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let key = Aes256Gcm::generate_key(OsRng);
+                write_private(&self.key_path, &key)?;
+                Ok(key)
+            }
+            Err(e) => Err(format!("Failed to read data key: {}", e)),
+        }
+    }
 
-        config.refresh_interval = Some(interval);
-        self.save_config(&config)
+    /// The key the API keys are encrypted with: derived from the passphrase
+    /// when the vault is enabled, the data key otherwise.
+    fn active_key(&self, config: &Config) -> Result<Key<Aes256Gcm>, String> {
+        match (&config.vault, &self.unlocked) {
+            (None, _) => self.data_key(),
+            (Some(_), Some(key)) => Ok(*key),
+            (Some(_), None) => Err("The vault is locked".to_string()),
+        }
+    }
+
+    /// Whether the API keys are protected by a passphrase not entered yet.
+    pub fn is_locked(&self) -> bool {
+        self.unlocked.is_none()
+            && self.load_config().is_ok_and(|config| config.vault.is_some())
+    }
+
+    /// Derives the vault key from `passphrase` and keeps it in memory for
+    /// the rest of the session.
+    pub fn unlock(&mut self, passphrase: &str) -> Result<(), String> {
+        let config = self.load_config()?;
+        let vault = config.vault.as_ref().ok_or("The vault is not enabled")?;
+        let key = vault.derive_key(passphrase)?;
+        decrypt(&key, &vault.check, &vault.check_nonce)
+            .map_err(|_| "Wrong passphrase".to_string())?;
+        self.unlocked = Some(key);
+        Ok(())
+    }
+
+    /// Protects the API keys with a passphrase and removes the data key.
+    pub fn enable_vault(&mut self, passphrase: &str) -> Result<(), String> {
+        if passphrase.is_empty() {
+            return Err("The passphrase cannot be empty".to_string());
+        }
+        let mut config = self.load_config()?;
+        if config.vault.is_some() {
+            return Err("The vault is already enabled".to_string());
+        }
+        let old_key = self.active_key(&config)?;
+        let (vault, key) = Vault::create(passphrase)?;
+        reencrypt(&mut config.keys, &old_key, &key)?;
+        config.vault = Some(vault);
+        self.save_config(&config)?;
+        self.unlocked = Some(key);
+
+        match fs::remove_file(&self.key_path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(format!("Failed to remove data key: {}", e))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Goes back to a data key file; the vault must be unlocked.
+    pub fn disable_vault(&mut self) -> Result<(), String> {
+        let mut config = self.load_config()?;
+        if config.vault.is_none() {
+            return Err("The vault is not enabled".to_string());
+        }
+        let old_key = self.active_key(&config)?;
+        reencrypt(&mut config.keys, &old_key, &self.data_key()?)?;
+        config.vault = None;
+        self.save_config(&config)?;
+        self.unlocked = None;
+        Ok(())
     }
 
     fn save_config(&self, config: &Config) -> Result<(), String> {
@@ -70,75 +242,441 @@ impl ApiKeyManager {
     }
 
     pub fn save_api_key(&self, service: &str, api_key: &str) -> Result<(), String> {
-        let cipher = Aes256Gcm::new_from_slice(&self.encryption_key)
-            .map_err(|e| format!("Failed to create cipher: {}", e))?;
-        
-        let nonce = Nonce::from_slice(b"unique nonce"); // TODO: Generate unique nonce per save
-        let encrypted_data = cipher.encrypt(nonce, api_key.as_bytes())
-            .map_err(|e| format!("Encryption failed: {}", e))?;
+        // Try to load existing config
+        let mut config = match fs::read_to_string(&self.config_path) {
+            Ok(_) => self.load_config()?,
+            Err(_) => Config::new(),
+        };
 
+        let (value, nonce) = encrypt(&self.active_key(&config)?, api_key)?;
         let new_key = EncryptedKey {
-            value: general_purpose::STANDARD.encode(encrypted_data),
-            nonce: general_purpose::STANDARD.encode(nonce),
+            value,
+            nonce,
             service: service.to_string(),
             created_at: chrono::Utc::now().to_rfc3339(),
             last_used: None,
         };
 
-        // Try to load existing config
-        let mut config = match fs::read_to_string(&self.config_path) {
-            Ok(data) => serde_json::from_str::<Config>(&data)
-                .map_err(|e| format!("Failed to parse config: {}", e))?,
-            Err(_) => Config {
-                keys: Vec::new(),
-                version: 1,
-                refresh_interval: Some(10),
-            },
-        };
-
         // Remove existing key for this service if it exists
         config.keys.retain(|k| k.service != service);
         config.keys.push(new_key);
 
-        let json = serde_json::to_string(&config)
-            .map_err(|e| format!("Failed to serialize config: {}", e))?;
-        
-        fs::write(&self.config_path, json)
-            .map_err(|e| format!("Failed to write config: {}", e))
+        self.save_config(&config)
     }
 
-    pub fn load_config(&self) -> Result<Config, String> {
-        let data = fs::read_to_string(&self.config_path)
-            .map_err(|e| format!("Failed to read config: {}", e))?;
-        
-        serde_json::from_str(&data)
-            .map_err(|e| format!("Failed to parse config: {}", e))
+    /// Upgrades the config if needed, failing if it cannot be read, e.g.
+    /// because a newer version wrote it. Having no config is fine.
+    pub fn check_config(&self) -> Result<(), String> {
+        if !self.config_path.exists() {
+            return Ok(());
+        }
+        self.load_config().map(|_| ())
     }
 
-    pub fn load_api_key(&self, service: &str) -> Result<String, String> {
+    /// Reads the config, upgrading it first if an older version wrote it.
+    /// Configs from newer versions are refused rather than misread.
+    pub fn load_config(&self) -> Result<Config, String> {
         let data = fs::read_to_string(&self.config_path)
             .map_err(|e| format!("Failed to read config: {}", e))?;
-        
-        let config: Config = serde_json::from_str(&data)
+
+        let mut json: Map<String, Value> = serde_json::from_str(&data)
+            .map_err(|e| format!("Failed to parse config: {}", e))?;
+        // Early configs may lack a version; they are version 1
+        let version = json.get("version").and_then(Value::as_u64).unwrap_or(1).max(1);
+        if version > CONFIG_VERSION as u64 {
+            return Err(format!(
+                "{} was written by a newer version of todoclist (config version {}, \
+                 this one reads up to {}), please update todoclist",
+                self.config_path.display(),
+                version,
+                CONFIG_VERSION
+            ));
+        }
+
+        let migrated = version < CONFIG_VERSION as u64;
+        if migrated {
+            self.migrate(&mut json, version as u8, &data)?;
+        }
+        let config: Config = serde_json::from_value(Value::Object(json))
             .map_err(|e| format!("Failed to parse config: {}", e))?;
+        if migrated {
+            self.save_config(&config)?;
+        }
+        Ok(config)
+    }
 
-        let cipher = Aes256Gcm::new_from_slice(&self.encryption_key)
-            .map_err(|e| format!("Failed to create cipher: {}", e))?;
+    /// Applies the migrations from `version` on, after backing the file up
+    /// as `config.v<version>.json.bak`. An existing backup is kept, it holds
+    /// the file from before an earlier, failed attempt.
+    fn migrate(
+        &self,
+        json: &mut Map<String, Value>,
+        version: u8,
+        original: &str,
+    ) -> Result<(), String> {
+        let backup = self.config_path.with_file_name(format!("config.v{}.json.bak", version));
+        if !backup.exists() {
+            write_private(&backup, original.as_bytes())?;
+        }
+        for migration in &MIGRATIONS[version as usize - 1..] {
+            (migration.apply)(self, json).map_err(|e| {
+                format!("Failed to migrate config from version {}: {}", migration.from, e)
+            })?;
+            json.insert("version".to_string(), Value::from(migration.from + 1));
+        }
+        Ok(())
+    }
+
+    /// Decrypts the key of `service` and records when it was last used.
+    pub fn load_api_key(&self, service: &str) -> Result<String, String> {
+        let mut config = self.load_config()?;
+        let active_key = self.active_key(&config)?;
 
-        let key = config.keys.iter()
+        let key = config.keys.iter_mut()
             .find(|k| k.service == service)
             .ok_or_else(|| format!("No API key found for service: {}", service))?;
 
-        let encrypted_data = general_purpose::STANDARD.decode(&key.value)
-            .map_err(|e| format!("Failed to decode encrypted key: {}", e))?;
+        let api_key = decrypt(&active_key, &key.value, &key.nonce)?;
+        key.last_used = Some(chrono::Utc::now().to_rfc3339());
+        // Only informational, a read-only config must not stop the app
+        let _ = self.save_config(&config);
+        Ok(api_key)
+    }
+
+    /// Deletes the key of `service`; returns whether there was one.
+    pub fn remove_api_key(&self, service: &str) -> Result<bool, String> {
+        let mut config = match fs::read_to_string(&self.config_path) {
+            Ok(_) => self.load_config()?,
+            Err(_) => return Ok(false),
+        };
+        let count = config.keys.len();
+        config.keys.retain(|k| k.service != service);
+        if config.keys.len() == count {
+            return Ok(false);
+        }
+        self.save_config(&config)?;
+        Ok(true)
+    }
+
+    /// The saved keys, without their secrets.
+    pub fn list_keys(&self) -> Result<Vec<KeyInfo>, String> {
+        let config = match fs::read_to_string(&self.config_path) {
+            Ok(_) => self.load_config()?,
+            Err(_) => return Ok(Vec::new()),
+        };
+        Ok(config.keys.iter()
+            .map(|k| KeyInfo {
+                service: k.service.clone(),
+                created_at: k.created_at.clone(),
+                last_used: k.last_used.clone(),
+            })
+            .collect())
+    }
+}
+
+/// A saved API key as shown to the user.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyInfo {
+    pub service: String,
+    pub created_at: String, // ISO 8601 timestamp
+    pub last_used: Option<String>, // ISO 8601 timestamp
+}
+
+impl KeyInfo {
+    /// One line such as `todoist: added 2024-05-01 09:30, last used 2024-05-02 18:00`.
+    pub fn summary(&self) -> String {
+        let last_used = self.last_used.as_deref().map_or("never".to_string(), local_time);
+        format!(
+            "{}: added {}, last used {}",
+            self.service,
+            local_time(&self.created_at),
+            last_used
+        )
+    }
+}
+
+/// Formats an RFC 3339 timestamp in local time, or leaves it as it is.
+fn local_time(timestamp: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .map(|time| time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|_| timestamp.to_string())
+}
+
+/// Encrypts a secret under a fresh random nonce, returning both encoded.
+fn encrypt(key: &Key<Aes256Gcm>, plaintext: &str) -> Result<(String, String), String> {
+    let cipher = Aes256Gcm::new(key);
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let encrypted_data = cipher.encrypt(&nonce, plaintext.as_bytes())
+        .map_err(|e| format!("Encryption failed: {}", e))?;
+
+    Ok((
+        general_purpose::STANDARD.encode(encrypted_data),
+        general_purpose::STANDARD.encode(nonce),
+    ))
+}
+
+fn decrypt(key: &Key<Aes256Gcm>, value: &str, nonce: &str) -> Result<String, String> {
+    let cipher = Aes256Gcm::new(key);
+
+    let encrypted_data = general_purpose::STANDARD.decode(value)
+        .map_err(|e| format!("Failed to decode encrypted key: {}", e))?;
+
+    let nonce = general_purpose::STANDARD.decode(nonce)
+        .map_err(|e| format!("Failed to decode nonce: {}", e))?;
+    if nonce.len() != LEGACY_NONCE.len() {
+        return Err("Invalid nonce length".to_string());
+    }
+
+    let decrypted_data = cipher.decrypt(Nonce::from_slice(&nonce), &encrypted_data[..])
+        .map_err(|e| format!("Decryption failed: {}", e))?;
+
+    String::from_utf8(decrypted_data)
+        .map_err(|e| format!("Failed to convert to string: {}", e))
+}
+
+/// Moves every API key in `config` from one encryption key to another.
+fn reencrypt(
+    keys: &mut [EncryptedKey],
+    from: &Key<Aes256Gcm>,
+    to: &Key<Aes256Gcm>,
+) -> Result<(), String> {
+    for key in keys {
+        let api_key = decrypt(from, &key.value, &key.nonce)?;
+        (key.value, key.nonce) = encrypt(to, &api_key)?;
+    }
+    Ok(())
+}
+
+/// Moves keys from the built-in legacy key to the data key, each under its
+/// own nonce.
+fn migrate_keys(manager: &ApiKeyManager, json: &mut Map<String, Value>) -> Result<(), String> {
+    let keys = json.get("keys").cloned().unwrap_or(Value::Array(Vec::new()));
+    let mut keys: Vec<EncryptedKey> =
+        serde_json::from_value(keys).map_err(|e| format!("Invalid keys: {}", e))?;
+    let legacy = Key::<Aes256Gcm>::from_slice(LEGACY_KEY);
+    reencrypt(&mut keys, legacy, &manager.data_key()?)?;
+    let keys = serde_json::to_value(keys).map_err(|e| format!("Invalid keys: {}", e))?;
+    json.insert("keys".to_string(), keys);
+    Ok(())
+}
+
+/// Moves the settings to a new settings file, or drops them if one exists
+/// already since it takes precedence.
+fn migrate_settings(manager: &ApiKeyManager, json: &mut Map<String, Value>) -> Result<(), String> {
+    let legacy: Map<String, Value> = LEGACY_SETTINGS
+        .iter()
+        .filter_map(|name| json.remove(*name).map(|value| (name.to_string(), value)))
+        .filter(|(_, value)| !value.is_null())
+        .collect();
+    let path = manager.config_path.with_file_name(SETTINGS_FILE);
+    if path.exists() {
+        return Ok(());
+    }
+    let mut settings: Settings = serde_json::from_value(Value::Object(legacy))
+        .map_err(|e| format!("Invalid settings: {}", e))?;
+    // Older versions accepted any interval, settings.toml has a minimum
+    settings.refresh_interval = settings.refresh_interval.max(MIN_REFRESH_INTERVAL);
+    settings.create(&path)
+}
+
+/// Creates a file only its owner can read and write.
+fn write_private(path: &Path, data: &[u8]) -> Result<(), String> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(path)
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    file.write_all(data)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_manager() -> ApiKeyManager {
+        let dir = std::env::temp_dir().join(format!("todoclist-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        ApiKeyManager::in_dir(&dir)
+    }
+
+    #[test]
+    fn test_keys_use_data_key_and_fresh_nonces() {
+        let manager = temp_manager();
+        manager.save_api_key("todoist", "secret").unwrap();
+        let first = manager.load_config().unwrap().keys.remove(0);
+        manager.save_api_key("todoist", "secret").unwrap();
+        let second = manager.load_config().unwrap().keys.remove(0);
+
+        assert_ne!(first.nonce, second.nonce);
+        assert_ne!(first.value, second.value);
+        assert_eq!(manager.load_api_key("todoist").unwrap(), "secret");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&manager.key_path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    /// A version 1 config holding a key encrypted with the legacy key.
+    fn legacy_config() -> Value {
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(LEGACY_KEY));
+        let value = cipher.encrypt(Nonce::from_slice(LEGACY_NONCE), &b"secret"[..]).unwrap();
+        serde_json::json!({
+            "keys": [{
+                "value": general_purpose::STANDARD.encode(value),
+                "nonce": general_purpose::STANDARD.encode(LEGACY_NONCE),
+                "service": "todoist",
+                "created_at": "2024-01-01T00:00:00+00:00",
+                "last_used": null,
+            }],
+            "version": 1,
+            "refresh_interval": 10,
+        })
+    }
+
+    #[test]
+    fn test_migrations_are_ordered() {
+        for (i, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.from as usize, i + 1);
+        }
+        assert_eq!(CONFIG_VERSION as usize, MIGRATIONS.len() + 1);
+    }
+
+    #[test]
+    fn test_keys_migration() {
+        let manager = temp_manager();
+        let Value::Object(mut json) = legacy_config() else { unreachable!() };
+        migrate_keys(&manager, &mut json).unwrap();
+
+        let keys: Vec<EncryptedKey> = serde_json::from_value(json["keys"].clone()).unwrap();
+        assert_ne!(keys[0].nonce, general_purpose::STANDARD.encode(LEGACY_NONCE));
+        let data_key = manager.data_key().unwrap();
+        assert_eq!(decrypt(&data_key, &keys[0].value, &keys[0].nonce).unwrap(), "secret");
+        assert_eq!(keys[0].created_at, "2024-01-01T00:00:00+00:00");
+    }
+
+    #[test]
+    fn test_settings_migration() {
+        let manager = temp_manager();
+        let settings_path = manager.config_path.with_file_name(SETTINGS_FILE);
+        let Value::Object(mut json) = serde_json::json!({
+            "keys": [],
+            "version": 2,
+            "refresh_interval": 10,
+            "vim_mode": true,
+            "theme": "paper",
+            "themes": { "paper": { "base": "light", "accent": "#005f87" } },
+            "layout": null,
+        }) else { unreachable!() };
+        migrate_settings(&manager, &mut json).unwrap();
+
+        assert_eq!(json.keys().collect::<Vec<_>>(), vec!["keys", "version"]);
+        let settings = Settings::load(&settings_path).unwrap();
+        assert_eq!(settings.refresh_interval, 10);
+        assert!(settings.vim_mode);
+        assert_eq!(settings.theme.as_deref(), Some("paper"));
+        assert_eq!(settings.themes["paper"].colors["accent"], "#005f87");
+
+        // An existing settings file is left alone
+        let mut json = Map::from_iter([("refresh_interval".to_string(), Value::from(60))]);
+        migrate_settings(&manager, &mut json).unwrap();
+        assert!(json.is_empty());
+        assert_eq!(Settings::load(&settings_path).unwrap().refresh_interval, 10);
+    }
+
+    #[test]
+    fn test_settings_migration_raises_short_intervals() {
+        // Older versions saved any interval, settings.toml refuses them
+        let manager = temp_manager();
+        let config = serde_json::json!({ "keys": [], "version": 2, "refresh_interval": 1 });
+        fs::write(&manager.config_path, config.to_string()).unwrap();
+        manager.load_config().unwrap();
+
+        let settings_path = manager.config_path.with_file_name(SETTINGS_FILE);
+        let settings = Settings::load(&settings_path).unwrap();
+        assert_eq!(settings.refresh_interval, MIN_REFRESH_INTERVAL);
+    }
+
+    #[test]
+    fn test_legacy_config_is_migrated_with_backup() {
+        let manager = temp_manager();
+        let original = legacy_config().to_string();
+        fs::write(&manager.config_path, &original).unwrap();
+
+        assert_eq!(manager.load_api_key("todoist").unwrap(), "secret");
+        let config = manager.load_config().unwrap();
+        assert_eq!(config.version, CONFIG_VERSION);
+        let saved = fs::read_to_string(&manager.config_path).unwrap();
+        assert!(!saved.contains("refresh_interval"));
+
+        let backup = manager.config_path.with_file_name("config.v1.json.bak");
+        assert_eq!(fs::read_to_string(backup).unwrap(), original);
+        let settings_path = manager.config_path.with_file_name(SETTINGS_FILE);
+        assert_eq!(Settings::load(&settings_path).unwrap().refresh_interval, 10);
+    }
+
+    #[test]
+    fn test_newer_config_is_refused() {
+        let manager = temp_manager();
+        let newer = serde_json::json!({ "keys": [], "version": CONFIG_VERSION + 1 });
+        fs::write(&manager.config_path, newer.to_string()).unwrap();
+
+        let error = manager.load_config().err().unwrap();
+        assert!(error.contains("newer version of todoclist"), "{}", error);
+        assert_eq!(
+            fs::read_to_string(&manager.config_path).unwrap(),
+            newer.to_string()
+        );
+    }
+
+    #[test]
+    fn test_key_usage_listing_and_removal() {
+        let manager = temp_manager();
+        assert!(manager.list_keys().unwrap().is_empty());
+        assert!(!manager.remove_api_key("todoist").unwrap());
+
+        manager.save_api_key("todoist", "secret").unwrap();
+        let keys = manager.list_keys().unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].last_used, None);
+        assert!(keys[0].summary().ends_with("last used never"));
+
+        manager.load_api_key("todoist").unwrap();
+        assert!(manager.list_keys().unwrap()[0].last_used.is_some());
+
+        assert!(manager.remove_api_key("todoist").unwrap());
+        assert!(manager.list_keys().unwrap().is_empty());
+        assert!(manager.load_api_key("todoist").is_err());
+    }
 
-        let nonce = general_purpose::STANDARD.decode(&key.nonce)
-            .map_err(|e| format!("Failed to decode nonce: {}", e))?;
+    #[test]
+    fn test_vault() {
+        let mut manager = temp_manager();
+        manager.save_api_key("todoist", "secret").unwrap();
+        manager.enable_vault("correct horse").unwrap();
+        assert!(!manager.key_path.exists());
+        assert_eq!(manager.load_api_key("todoist").unwrap(), "secret");
 
-        let decrypted_data = cipher.decrypt(Nonce::from_slice(&nonce), &encrypted_data[..])
-            .map_err(|e| format!("Decryption failed: {}", e))?;
+        // A new session starts locked and only opens with the passphrase
+        let mut session = ApiKeyManager::in_dir(manager.config_path.parent().unwrap());
+        assert!(session.is_locked());
+        assert!(session.load_api_key("todoist").is_err());
+        assert_eq!(session.unlock("wrong").unwrap_err(), "Wrong passphrase");
+        session.unlock("correct horse").unwrap();
+        assert!(!session.is_locked());
+        assert_eq!(session.load_api_key("todoist").unwrap(), "secret");
 
-        String::from_utf8(decrypted_data)
-            .map_err(|e| format!("Failed to convert to string: {}", e))
+        session.disable_vault().unwrap();
+        let session = ApiKeyManager::in_dir(manager.config_path.parent().unwrap());
+        assert!(!session.is_locked());
+        assert_eq!(session.load_api_key("todoist").unwrap(), "secret");
     }
 }
@@ -1,98 +1,511 @@
 use crate::app::App;
-use crossterm::event::{KeyCode, KeyEvent};
-use crate::todoist::PendingChange;
-use crate::todoist::PendingChange::TaskCompletion;
+use crate::input::{Edit, TextInput};
+use crate::keymap::{Action, FormAction, Key, KeyMatch, Mode};
+use crate::layout::{Hit, Pane};
+use crate::palette::PaletteState;
+use crate::prompt::PromptKind;
+use crate::toast::Severity;
+use crossterm::event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
 
 pub async fn handle_key_events(key_event: KeyEvent, app: &mut App) {
-    if !app.onboarding_complete {
-        match key_event.code {
-            KeyCode::Char(c) => {
-                app.input_buffer.push(c);
+    match app.mode() {
+        Mode::Onboarding => handle_onboarding_key_events(key_event, app),
+        Mode::Help => {
+            // Any key closes the help overlay
+            app.show_help = false;
+        }
+        Mode::Search => handle_search_key_events(key_event, app),
+        Mode::Palette => handle_palette_key_events(key_event, app).await,
+        Mode::Prompt => handle_prompt_key_events(key_event, app),
+        Mode::CommandLine => handle_command_line_key_events(key_event, app).await,
+        Mode::Calendar => handle_calendar_key_events(key_event, app),
+        Mode::Agenda => handle_agenda_key_events(key_event, app),
+        Mode::List => handle_list_key_events(key_event, app).await,
+    }
+}
+
+/// Clicks select tasks and focus panes, a click on ☐ completes the task and
+/// the wheel moves through the list under the mouse.
+pub fn handle_mouse_events(mouse_event: MouseEvent, app: &mut App) {
+    // Overlays and forms are keyboard only
+    if app.mode() != Mode::List {
+        return;
+    }
+    let Some(hit) = app.rendered.hit(mouse_event.column, mouse_event.row) else {
+        return;
+    };
+
+    match mouse_event.kind {
+        MouseEventKind::Down(MouseButton::Left) => {
+            app.focus_pane(hit.pane());
+            if let Hit::Row {
+                index, checkbox, ..
+            } = hit
+            {
+                if index >= app.focused_tasks().len() {
+                    return;
+                }
+                app.select_index(index);
+                if let (true, Some(task_id)) = (checkbox, app.selected_task.clone()) {
+                    let _ = app.toggle_tasks_completion(&[task_id]);
+                }
             }
-            KeyCode::Backspace => {
-                app.input_buffer.pop();
+        }
+        MouseEventKind::ScrollDown => {
+            app.focus_pane(hit.pane());
+            app.select_index(app.selected_index().map_or(0, |i| i + 1));
+        }
+        MouseEventKind::ScrollUp => {
+            app.focus_pane(hit.pane());
+            app.select_index(app.selected_index().map_or(0, |i| i.saturating_sub(1)));
+        }
+        _ => {}
+    }
+}
+
+/// Pasted text goes into the open text field in one piece, so a long API
+/// key is not typed key by key.
+pub fn handle_paste_events(text: &str, app: &mut App) {
+    match app.mode() {
+        Mode::Onboarding if app.key_check.is_none() => {
+            app.input_buffer.insert_str(text.trim());
+            app.key_error = None;
+        }
+        Mode::Search => {
+            if let Some(search) = &mut app.search {
+                search.query.insert_str(text);
             }
-            KeyCode::Enter => {
-                if app.is_valid_api_key() {
-                    if let Err(e) = app
-                        .api_key_manager
-                        .save_api_key("todoist", &app.input_buffer)
-                    {
-                        eprintln!("Failed to save API key: {}", e);
-                    } else {
-                        app.api_key = Some(app.input_buffer.clone());
-                        app.onboarding_complete = true;
-                    }
-                }
+            app.update_search();
+        }
+        Mode::Palette => {
+            if let Some(palette) = &mut app.palette {
+                palette.query.insert_str(text);
+                palette.update();
+            }
+        }
+        Mode::Prompt => {
+            if let Some(prompt) = &mut app.prompt {
+                prompt.input.insert_str(text);
             }
-            _ => {}
+        }
+        Mode::CommandLine => {
+            if let Some(command) = &mut app.command_line {
+                command.insert_str(text);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn handle_onboarding_key_events(key_event: KeyEvent, app: &mut App) {
+    let action = Mode::Onboarding.lookup(Key::from(key_event));
+    // While Todoist checks the key, cancelling the check is all there is to do
+    if app.key_check.is_some() {
+        if action == Some(FormAction::Cancel) {
+            app.key_check = None;
         }
         return;
     }
 
-    match key_event.code {
-        KeyCode::Char('q') => {
-            let changes: Vec<PendingChange> = app.pending_changes.iter().cloned().collect();
-            app.pending_changes = vec![];
-            for change in &changes {
-                match change {
-                    TaskCompletion { task_id, completed } => {
-                        if let Some(client) = &app.todoist_client {
-                            let _ = client.update_task_completion(&task_id, *completed).await;
-                        }
+    match action {
+        Some(FormAction::Submit) if app.vault_locked && app.is_valid_api_key() => {
+            let passphrase = app.input_buffer.take();
+            match app.unlock(&passphrase) {
+                Ok(()) => app.toasts.push(Severity::Success, "Vault unlocked"),
+                Err(e) => app.toasts.push(Severity::Error, e),
+            }
+        }
+        Some(FormAction::Submit) if app.is_valid_api_key() => app.check_api_key(),
+        Some(FormAction::DeleteChar) => {
+            app.input_buffer.edit(Edit::DeleteBack);
+            app.key_error = None;
+        }
+        Some(FormAction::ToggleReveal) => app.input_buffer.toggle_reveal(),
+        // Changing the key can be abandoned, keeping the current one
+        Some(FormAction::Cancel) if app.api_key.is_some() => {
+            app.input_buffer.clear();
+            app.key_error = None;
+            app.onboarding_complete = true;
+        }
+        Some(FormAction::Cancel) => app.quit(),
+        Some(_) => {}
+        None => {
+            if app.input_buffer.handle_key(Key::from(key_event)) {
+                app.key_error = None;
+            }
+        }
+    }
+}
+
+async fn handle_list_key_events(key_event: KeyEvent, app: &mut App) {
+    app.message = None;
+
+    // Counts only start on the first key, so chords like `g 0` still work
+    if app.vim_mode && app.pending_keys.is_empty() && key_event.modifiers.is_empty() {
+        if let KeyCode::Char(c @ '0'..='9') = key_event.code {
+            if c != '0' || app.count.is_some() {
+                let digit = c.to_digit(10).unwrap_or(0) as usize;
+                app.count = Some((app.count.unwrap_or(0) * 10 + digit).min(9999));
+                return;
+            }
+        }
+    }
+
+    let key = Key::from(key_event);
+    app.pending_keys.push(key);
+    let mut lookup = app.keymap.lookup(&app.pending_keys);
+    if lookup == KeyMatch::None && app.pending_keys.len() > 1 {
+        // An unfinished chord followed by an unrelated key: drop the chord
+        // and try the new key on its own.
+        app.pending_keys = vec![key];
+        lookup = app.keymap.lookup(&app.pending_keys);
+    }
+
+    match lookup {
+        KeyMatch::Action(action) => {
+            app.pending_keys.clear();
+            match (action, app.count.take()) {
+                // `5G` and `5gg` jump to the fifth task, as in vim
+                (Action::FirstTask | Action::LastTask, Some(n)) => {
+                    app.select_index(n.saturating_sub(1));
+                }
+                // `5x` and `5dd` act on five tasks as a single undo step
+                (action @ (Action::ToggleComplete | Action::DeleteTask), Some(n)) => {
+                    let task_ids = app.counted_task_ids(n);
+                    change_tasks(action, &task_ids, app);
+                }
+                (action, Some(n)) if action.is_repeatable() => {
+                    for _ in 0..n {
+                        dispatch_action(action, app).await;
                     }
                 }
+                (action, _) => dispatch_action(action, app).await,
             }
-            app.running = false;
         }
-        KeyCode::Up => {
+        KeyMatch::Prefix => {}
+        KeyMatch::None => {
+            app.pending_keys.clear();
+            app.count = None;
+        }
+    }
+}
+
+/// Runs an action regardless of which keys triggered it.
+pub async fn dispatch_action(action: Action, app: &mut App) {
+    // Quitting with unsent changes only goes ahead when repeated right away
+    let quit_requested = std::mem::take(&mut app.quit_requested);
+    match action {
+        Action::Quit => {
+            app.quit_requested = quit_requested;
+            app.flush_then(Action::Quit);
+        }
+        Action::Search => {
+            app.open_search();
+        }
+        Action::CommandLine => {
+            app.command_line = Some(TextInput::default());
+        }
+        Action::Palette => {
+            app.palette = Some(PaletteState::default());
+        }
+        Action::Calendar => app.open_calendar(),
+        Action::Agenda => app.open_agenda(),
+        Action::SyncNow => {
+            // The sync loop flushes queued changes before refreshing
+            app.refresh_requested.notify_one();
+        }
+        Action::Help => {
+            app.show_help = true;
+        }
+        Action::NextTheme => {
+            app.next_theme();
+        }
+        Action::SwitchProfile => app.open_prompt(PromptKind::SwitchProfile),
+        Action::ChangeApiKey => app.change_api_key(),
+        Action::Logout => app.flush_then(Action::Logout),
+        Action::PreviousTask => {
             app.previous();
         }
-        KeyCode::Down => {
+        Action::NextTask => {
             app.next();
         }
-        KeyCode::Left => {
-            // Move to Today list
-            if app.today_list_state.selected().is_none() && !app.today_tasks().is_empty() {
-                app.today_list_state.select(Some(0));
-                let selected_task_id = app.today_tasks().get(0).unwrap().id.clone();
-                app.selected_task = app
-                    .tasks
-                    .iter()
-                    .filter(|task| task.id == selected_task_id)
-                    .map(|task| task.id.clone())
-                    .next()
+        Action::FirstTask => {
+            app.select_first();
+        }
+        Action::LastTask => {
+            app.select_last();
+        }
+        Action::PageDown => app.page(1),
+        Action::PageUp => app.page(-1),
+        Action::FocusToday => {
+            app.focus_pane(Pane::Today);
+        }
+        Action::FocusInbox => {
+            app.focus_pane(Pane::Inbox);
+        }
+        Action::NextPane => {
+            app.focus_pane(app.focused_pane().other());
+        }
+        Action::ZoomPane => {
+            app.toggle_zoom();
+        }
+        Action::HidePane => {
+            app.toggle_hidden();
+        }
+        Action::ToggleComplete | Action::DeleteTask => {
+            let task_ids = app.target_task_ids();
+            change_tasks(action, &task_ids, app);
+        }
+        Action::EditTask => {
+            app.open_prompt(PromptKind::EditContent);
+        }
+        Action::RescheduleTask => {
+            app.open_prompt(PromptKind::Reschedule);
+        }
+        Action::MoveTask => {
+            app.open_prompt(PromptKind::MoveToProject);
+        }
+        Action::SetPriority => {
+            app.open_prompt(PromptKind::SetPriority);
+        }
+        Action::AddLabel => {
+            app.open_prompt(PromptKind::AddLabel);
+        }
+        Action::ToggleMark => {
+            app.toggle_mark();
+        }
+        Action::SelectRange => {
+            app.toggle_range();
+        }
+        Action::SelectAll => {
+            app.mark_all();
+        }
+        Action::ClearMarks => {
+            app.clear_marks();
+        }
+        Action::Undo => {
+            app.toasts.dismiss_action(Action::Undo);
+            app.undo();
+        }
+        Action::Redo => {
+            app.redo();
+        }
+    }
+}
+
+/// Completes or deletes tasks, all in one undo step.
+fn change_tasks(action: Action, task_ids: &[String], app: &mut App) {
+    if task_ids.is_empty() {
+        return;
+    }
+    let _ = match action {
+        Action::DeleteTask => app.delete_tasks(task_ids),
+        _ => app.toggle_tasks_completion(task_ids),
+    };
+}
+
+async fn handle_palette_key_events(key_event: KeyEvent, app: &mut App) {
+    let Some(palette) = &mut app.palette else {
+        return;
+    };
+    match Mode::Palette.lookup(Key::from(key_event)) {
+        Some(FormAction::Cancel) => {
+            app.palette = None;
+        }
+        Some(FormAction::Submit) => {
+            let action = palette.selected();
+            app.palette = None;
+            if let Some(action) = action {
+                dispatch_action(action, app).await;
             }
-            app.list_state.select(None);
-        }
-        KeyCode::Right => {
-            // Move to Inbox list
-            if app.list_state.selected().is_none() && !app.tasks.is_empty() {
-                app.list_state.select(Some(0));
-                app.selected_task = app
-                    .tasks
+        }
+        Some(FormAction::Previous) => palette.previous(),
+        Some(FormAction::Next) => palette.next(),
+        Some(FormAction::DeleteChar) => {
+            palette.query.edit(Edit::DeleteBack);
+            palette.update();
+        }
+        Some(_) => {}
+        None => {
+            if palette.query.handle_key(Key::from(key_event)) {
+                palette.update();
+            }
+        }
+    }
+}
+
+async fn handle_command_line_key_events(key_event: KeyEvent, app: &mut App) {
+    let Some(command) = &mut app.command_line else {
+        return;
+    };
+    match Mode::CommandLine.lookup(Key::from(key_event)) {
+        Some(FormAction::Cancel) => {
+            app.command_line = None;
+        }
+        Some(FormAction::Submit) => {
+            let command = command.value().trim().to_string();
+            app.command_line = None;
+            execute_command(&command, app).await;
+        }
+        // Backspace on an empty command line leaves it, as in vim
+        Some(FormAction::DeleteChar) if command.is_empty() => {
+            app.command_line = None;
+        }
+        Some(FormAction::DeleteChar) => command.edit(Edit::DeleteBack),
+        Some(_) => {}
+        None => {
+            command.handle_key(Key::from(key_event));
+        }
+    }
+}
+
+/// Runs a `:` command such as `:q`, `:w` or `:12`.
+async fn execute_command(command: &str, app: &mut App) {
+    if let Ok(line) = command.parse::<usize>() {
+        app.select_index(line.saturating_sub(1));
+        return;
+    }
+
+    match command {
+        "" => {}
+        "q" | "q!" | "quit" | "wq" | "x" => dispatch_action(Action::Quit, app).await,
+        "w" | "sync" => {
+            let count = app.pending_changes.len();
+            dispatch_action(Action::SyncNow, app).await;
+            app.toasts
+                .push(Severity::Info, format!("Syncing {} change(s)", count));
+        }
+        "u" | "undo" => dispatch_action(Action::Undo, app).await,
+        "red" | "redo" => dispatch_action(Action::Redo, app).await,
+        "today" => dispatch_action(Action::FocusToday, app).await,
+        "inbox" => dispatch_action(Action::FocusInbox, app).await,
+        "h" | "help" => dispatch_action(Action::Help, app).await,
+        "theme" => {
+            let names: Vec<&str> = app.themes.iter().map(|theme| theme.name.as_str()).collect();
+            app.message = Some(format!("Themes: {}", names.join(", ")));
+        }
+        _ if command.starts_with("theme ") => {
+            if let Err(e) = app.set_theme(command["theme ".len()..].trim()) {
+                app.message = Some(e);
+            }
+        }
+        "logout" => dispatch_action(Action::Logout, app).await,
+        "keys" => {
+            app.message = Some(match app.api_key_manager.list_keys() {
+                Ok(keys) if keys.is_empty() => "No saved API keys".to_string(),
+                Ok(keys) => keys
                     .iter()
-                    .filter(|task| !task.is_completed)
-                    .filter(|task| {
-                        if let Some(due) = &task.due {
-                            let today = chrono::Local::now().date_naive();
-                            let task_date =
-                                chrono::NaiveDate::parse_from_str(&due.date, "%Y-%m-%d").ok();
-                            task_date == Some(today)
-                        } else {
-                            false
-                        }
-                    })
-                    .map(|task| task.id.clone())
-                    .next();
+                    .map(|key| key.summary())
+                    .collect::<Vec<_>>()
+                    .join("; "),
+                Err(e) => e,
+            });
+        }
+        "profile" => {
+            let names = app.profile.names();
+            app.message = Some(format!(
+                "Profile: {} (available: {})",
+                app.profile.name,
+                names.join(", ")
+            ));
+        }
+        _ if command.starts_with("profile ") => {
+            if let Err(e) = app.switch_profile(command["profile ".len()..].trim()) {
+                app.message = Some(e);
             }
-            app.today_list_state.select(None);
         }
-        KeyCode::Char(' ') => {
-            if let Some(selected_task_id) = &app.selected_task {
-                let _ = app.toggle_task_completion(selected_task_id.clone()).await;
+        _ => app.message = Some(format!("Not a command: {}", command)),
+    }
+}
+
+fn handle_prompt_key_events(key_event: KeyEvent, app: &mut App) {
+    let Some(prompt) = &mut app.prompt else {
+        return;
+    };
+    match Mode::Prompt.lookup(Key::from(key_event)) {
+        Some(FormAction::Cancel) => {
+            app.prompt = None;
+        }
+        Some(FormAction::Submit) => {
+            app.submit_prompt();
+        }
+        Some(FormAction::DeleteChar) => prompt.input.edit(Edit::DeleteBack),
+        Some(_) => {}
+        None => {
+            prompt.input.handle_key(Key::from(key_event));
+        }
+    }
+}
+
+fn handle_calendar_key_events(key_event: KeyEvent, app: &mut App) {
+    let Some(calendar) = &mut app.calendar else {
+        return;
+    };
+    match Mode::Calendar.lookup(Key::from(key_event)) {
+        // Escape first drops a picked up task, then closes the calendar
+        Some(FormAction::Cancel) if calendar.moving.is_some() => {
+            calendar.moving = None;
+        }
+        Some(FormAction::Cancel) => {
+            app.calendar = None;
+        }
+        Some(FormAction::Submit) => app.calendar_submit(),
+        Some(FormAction::Next) => app.calendar_select(1),
+        Some(FormAction::Previous) => app.calendar_select(-1),
+        Some(FormAction::NextDay) => calendar.move_days(1),
+        Some(FormAction::PreviousDay) => calendar.move_days(-1),
+        Some(FormAction::NextWeek) => calendar.move_days(7),
+        Some(FormAction::PreviousWeek) => calendar.move_days(-7),
+        Some(FormAction::NextMonth) => calendar.move_months(1),
+        Some(FormAction::PreviousMonth) => calendar.move_months(-1),
+        Some(FormAction::Today) => calendar.select_date(chrono::Local::now().date_naive()),
+        Some(FormAction::DeleteChar | FormAction::ToggleReveal) | None => {}
+    }
+}
+
+fn handle_agenda_key_events(key_event: KeyEvent, app: &mut App) {
+    let Some(agenda) = &mut app.agenda else {
+        return;
+    };
+    match Mode::Agenda.lookup(Key::from(key_event)) {
+        Some(FormAction::Cancel) => app.agenda = None,
+        Some(FormAction::NextDay) => agenda.move_days(1),
+        Some(FormAction::PreviousDay) => agenda.move_days(-1),
+        Some(FormAction::NextWeek) => agenda.move_days(7),
+        Some(FormAction::PreviousWeek) => agenda.move_days(-7),
+        Some(FormAction::Next) => agenda.scroll(1),
+        Some(FormAction::Previous) => agenda.scroll(-1),
+        Some(FormAction::Today) => agenda.date = chrono::Local::now().date_naive(),
+        Some(_) | None => {}
+    }
+}
+
+fn handle_search_key_events(key_event: KeyEvent, app: &mut App) {
+    let Some(search) = &mut app.search else {
+        return;
+    };
+    match Mode::Search.lookup(Key::from(key_event)) {
+        Some(FormAction::Cancel) => {
+            app.search = None;
+        }
+        Some(FormAction::Submit) => {
+            app.confirm_search();
+        }
+        Some(FormAction::Previous) => {
+            search.previous();
+        }
+        Some(FormAction::Next) => {
+            search.next();
+        }
+        Some(FormAction::DeleteChar) => {
+            search.query.edit(Edit::DeleteBack);
+            app.update_search();
+        }
+        Some(_) => {}
+        None => {
+            if search.query.handle_key(Key::from(key_event)) {
+                app.update_search();
             }
         }
-        _ => {}
     }
 }
@@ -0,0 +1,321 @@
+use crossterm::event::{KeyCode, KeyModifiers};
+use ratatui::{
+    style::{Modifier, Style},
+    text::{Line, Span},
+};
+
+use crate::keymap::{merge_hints, Key};
+
+/// Shown instead of each character of a masked input.
+const MASK: char = '•';
+
+/// A cursor movement or deletion in a [`TextInput`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edit {
+    Left,
+    Right,
+    WordLeft,
+    WordRight,
+    Start,
+    End,
+    DeleteBack,
+    DeleteForward,
+    DeleteWordBack,
+    DeleteWordForward,
+    DeleteToStart,
+    DeleteToEnd,
+}
+
+#[derive(Debug)]
+struct EditKey {
+    key: Key,
+    edit: Edit,
+    hint: &'static str,
+}
+
+const fn edit_key(
+    code: KeyCode,
+    modifiers: KeyModifiers,
+    edit: Edit,
+    hint: &'static str,
+) -> EditKey {
+    EditKey {
+        key: Key { code, modifiers },
+        edit,
+        hint,
+    }
+}
+
+const fn plain(code: KeyCode, edit: Edit, hint: &'static str) -> EditKey {
+    edit_key(code, KeyModifiers::NONE, edit, hint)
+}
+
+const fn ctrl(code: KeyCode, edit: Edit, hint: &'static str) -> EditKey {
+    edit_key(code, KeyModifiers::CONTROL, edit, hint)
+}
+
+const fn alt(code: KeyCode, edit: Edit, hint: &'static str) -> EditKey {
+    edit_key(code, KeyModifiers::ALT, edit, hint)
+}
+
+/// Editing keys shared by every text field, in the style of shells and
+/// readline. Keys a form binds itself, e.g. backspace, take precedence.
+const EDIT_KEYS: &[EditKey] = &[
+    plain(KeyCode::Left, Edit::Left, "move"),
+    plain(KeyCode::Right, Edit::Right, "move"),
+    ctrl(KeyCode::Left, Edit::WordLeft, "word"),
+    ctrl(KeyCode::Right, Edit::WordRight, "word"),
+    alt(KeyCode::Char('b'), Edit::WordLeft, "word"),
+    alt(KeyCode::Char('f'), Edit::WordRight, "word"),
+    plain(KeyCode::Home, Edit::Start, "start/end"),
+    plain(KeyCode::End, Edit::End, "start/end"),
+    ctrl(KeyCode::Char('a'), Edit::Start, "start/end"),
+    ctrl(KeyCode::Char('e'), Edit::End, "start/end"),
+    plain(KeyCode::Backspace, Edit::DeleteBack, "delete"),
+    plain(KeyCode::Delete, Edit::DeleteForward, "delete"),
+    ctrl(KeyCode::Char('w'), Edit::DeleteWordBack, "delete word"),
+    alt(KeyCode::Backspace, Edit::DeleteWordBack, "delete word"),
+    ctrl(KeyCode::Backspace, Edit::DeleteWordBack, "delete word"),
+    alt(KeyCode::Char('d'), Edit::DeleteWordForward, "delete word"),
+    ctrl(KeyCode::Char('u'), Edit::DeleteToStart, "clear to start"),
+    ctrl(KeyCode::Char('k'), Edit::DeleteToEnd, "clear to end"),
+];
+
+/// Editing keys for the help overlay, as `(keys, label)`.
+pub fn edit_hints() -> Vec<(String, &'static str)> {
+    merge_hints(
+        EDIT_KEYS
+            .iter()
+            .map(|edit_key| (edit_key.key.to_string(), edit_key.hint))
+            .collect(),
+    )
+}
+
+/// A one-line text field with a cursor, used by every form.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TextInput {
+    value: String,
+    /// Cursor position in characters, at most the length of the value.
+    cursor: usize,
+    /// Whether the value is shown as dots, e.g. for API keys.
+    masked: bool,
+    /// Whether a masked value is shown in clear for now.
+    revealed: bool,
+}
+
+impl TextInput {
+    /// An input holding `value`, with the cursor at the end.
+    pub fn new(value: impl Into<String>) -> Self {
+        let value = value.into();
+        Self {
+            cursor: value.chars().count(),
+            value,
+            ..Self::default()
+        }
+    }
+
+    /// An empty input whose value is hidden until revealed.
+    pub fn masked() -> Self {
+        Self {
+            masked: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.value.is_empty()
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn clear(&mut self) {
+        self.value.clear();
+        self.cursor = 0;
+    }
+
+    /// Empties the input, returning what it held.
+    pub fn take(&mut self) -> String {
+        self.cursor = 0;
+        std::mem::take(&mut self.value)
+    }
+
+    /// Shows a masked value in clear, or hides it again.
+    pub fn toggle_reveal(&mut self) {
+        self.revealed = !self.revealed;
+    }
+
+    pub fn insert(&mut self, c: char) {
+        let index = self.byte_index(self.cursor);
+        self.value.insert(index, c);
+        self.cursor += 1;
+    }
+
+    /// Inserts pasted text, dropping line breaks and other control
+    /// characters a one-line field cannot hold.
+    pub fn insert_str(&mut self, text: &str) {
+        for c in text.chars().filter(|c| !c.is_control()) {
+            self.insert(c);
+        }
+    }
+
+    /// Applies an editing key; returns `false` for keys that do not edit,
+    /// leaving them to the form.
+    pub fn handle_key(&mut self, key: Key) -> bool {
+        if let Some(edit_key) = EDIT_KEYS.iter().find(|edit_key| edit_key.key == key) {
+            self.edit(edit_key.edit);
+            return true;
+        }
+        match key.code {
+            KeyCode::Char(c) if key.modifiers.is_empty() => {
+                self.insert(c);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn edit(&mut self, edit: Edit) {
+        let len = self.value.chars().count();
+        match edit {
+            Edit::Left => self.cursor = self.cursor.saturating_sub(1),
+            Edit::Right => self.cursor = (self.cursor + 1).min(len),
+            Edit::WordLeft => self.cursor = self.word_start(),
+            Edit::WordRight => self.cursor = self.word_end(),
+            Edit::Start => self.cursor = 0,
+            Edit::End => self.cursor = len,
+            Edit::DeleteBack => self.delete(self.cursor.saturating_sub(1), self.cursor),
+            Edit::DeleteForward => self.delete(self.cursor, (self.cursor + 1).min(len)),
+            Edit::DeleteWordBack => self.delete(self.word_start(), self.cursor),
+            Edit::DeleteWordForward => self.delete(self.cursor, self.word_end()),
+            Edit::DeleteToStart => self.delete(0, self.cursor),
+            Edit::DeleteToEnd => self.delete(self.cursor, len),
+        }
+    }
+
+    /// Removes the characters from `start` up to `end` and puts the cursor
+    /// where they were.
+    fn delete(&mut self, start: usize, end: usize) {
+        let range = self.byte_index(start)..self.byte_index(end);
+        self.value.replace_range(range, "");
+        self.cursor = start;
+    }
+
+    fn byte_index(&self, cursor: usize) -> usize {
+        self.value
+            .char_indices()
+            .nth(cursor)
+            .map_or(self.value.len(), |(index, _)| index)
+    }
+
+    /// Start of the word before the cursor, skipping whitespace first.
+    fn word_start(&self) -> usize {
+        let chars: Vec<char> = self.value.chars().collect();
+        let mut i = self.cursor;
+        while i > 0 && chars[i - 1].is_whitespace() {
+            i -= 1;
+        }
+        while i > 0 && !chars[i - 1].is_whitespace() {
+            i -= 1;
+        }
+        i
+    }
+
+    /// End of the word after the cursor, skipping whitespace first.
+    fn word_end(&self) -> usize {
+        let chars: Vec<char> = self.value.chars().collect();
+        let mut i = self.cursor;
+        while i < chars.len() && chars[i].is_whitespace() {
+            i += 1;
+        }
+        while i < chars.len() && !chars[i].is_whitespace() {
+            i += 1;
+        }
+        i
+    }
+
+    /// The value as displayed: masked unless revealed.
+    pub fn display(&self) -> String {
+        if self.masked && !self.revealed {
+            self.value.chars().map(|_| MASK).collect()
+        } else {
+            self.value.clone()
+        }
+    }
+
+    /// The displayed value with the character under the cursor reversed,
+    /// or `placeholder` while the input is empty.
+    pub fn line(&self, placeholder: Span<'static>) -> Line<'static> {
+        let cursor_style = Style::default().add_modifier(Modifier::REVERSED);
+        if self.value.is_empty() {
+            return Line::from(vec![Span::styled(" ", cursor_style), placeholder]);
+        }
+        let display: Vec<char> = self.display().chars().collect();
+        let before: String = display[..self.cursor].iter().collect();
+        let under = display
+            .get(self.cursor)
+            .map_or(" ".to_string(), char::to_string);
+        let after: String = display
+            .get(self.cursor + 1..)
+            .unwrap_or_default()
+            .iter()
+            .collect();
+        Line::from(vec![
+            Span::raw(before),
+            Span::styled(under, cursor_style),
+            Span::raw(after),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(input: &mut TextInput, keys: &str) {
+        for key in keys.split_whitespace() {
+            assert!(input.handle_key(Key::parse(key).unwrap()), "{}", key);
+        }
+    }
+
+    #[test]
+    fn test_cursor_editing() {
+        let mut input = TextInput::new("buy milk");
+        press(&mut input, "ctrl-left ctrl-left ctrl-right");
+        assert_eq!(input.cursor(), 3);
+        press(&mut input, "space o a t");
+        assert_eq!(input.value(), "buy oat milk");
+
+        press(&mut input, "ctrl-w");
+        assert_eq!(input.value(), "buy  milk");
+        press(&mut input, "delete ctrl-k");
+        assert_eq!(input.value(), "buy ");
+        press(&mut input, "home alt-d");
+        assert_eq!(input.value(), " ");
+        assert!(!input.handle_key(Key::parse("enter").unwrap()));
+
+        // Multi-byte characters move and delete as one
+        let mut input = TextInput::new("café");
+        press(&mut input, "left backspace");
+        assert_eq!(input.value(), "caé");
+        press(&mut input, "ctrl-u");
+        assert_eq!((input.value(), input.cursor()), ("é", 0));
+    }
+
+    #[test]
+    fn test_paste_and_masking() {
+        let mut input = TextInput::masked();
+        input.insert_str("0123abcd\r\n");
+        assert_eq!(input.value(), "0123abcd");
+        assert_eq!(input.display(), "••••••••");
+        input.toggle_reveal();
+        assert_eq!(input.display(), "0123abcd");
+        assert_eq!(input.take(), "0123abcd");
+        assert!(input.is_empty());
+    }
+}
@@ -0,0 +1,873 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Everything the user can trigger from the task lists, independent of the
+/// keys bound to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    NextTask,
+    PreviousTask,
+    FocusToday,
+    FocusInbox,
+    NextPane,
+    ZoomPane,
+    HidePane,
+    FirstTask,
+    LastTask,
+    PageDown,
+    PageUp,
+    ToggleComplete,
+    DeleteTask,
+    EditTask,
+    RescheduleTask,
+    MoveTask,
+    SetPriority,
+    AddLabel,
+    ToggleMark,
+    SelectRange,
+    SelectAll,
+    ClearMarks,
+    Undo,
+    Redo,
+    Search,
+    CommandLine,
+    Palette,
+    Calendar,
+    Agenda,
+    SyncNow,
+    NextTheme,
+    SwitchProfile,
+    ChangeApiKey,
+    Logout,
+    Help,
+}
+
+impl Action {
+    pub const ALL: &'static [Action] = &[
+        Action::Quit,
+        Action::NextTask,
+        Action::PreviousTask,
+        Action::FocusToday,
+        Action::FocusInbox,
+        Action::NextPane,
+        Action::ZoomPane,
+        Action::HidePane,
+        Action::FirstTask,
+        Action::LastTask,
+        Action::PageDown,
+        Action::PageUp,
+        Action::ToggleComplete,
+        Action::DeleteTask,
+        Action::EditTask,
+        Action::RescheduleTask,
+        Action::MoveTask,
+        Action::SetPriority,
+        Action::AddLabel,
+        Action::ToggleMark,
+        Action::SelectRange,
+        Action::SelectAll,
+        Action::ClearMarks,
+        Action::Undo,
+        Action::Redo,
+        Action::Search,
+        Action::CommandLine,
+        Action::Palette,
+        Action::Calendar,
+        Action::Agenda,
+        Action::SyncNow,
+        Action::NextTheme,
+        Action::SwitchProfile,
+        Action::ChangeApiKey,
+        Action::Logout,
+        Action::Help,
+    ];
+
+    /// Identifier used for the action in the settings file.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::NextTask => "next_task",
+            Action::PreviousTask => "previous_task",
+            Action::FocusToday => "focus_today",
+            Action::FocusInbox => "focus_inbox",
+            Action::NextPane => "next_pane",
+            Action::ZoomPane => "zoom_pane",
+            Action::HidePane => "hide_pane",
+            Action::FirstTask => "first_task",
+            Action::LastTask => "last_task",
+            Action::PageDown => "page_down",
+            Action::PageUp => "page_up",
+            Action::ToggleComplete => "toggle_complete",
+            Action::DeleteTask => "delete_task",
+            Action::EditTask => "edit_task",
+            Action::RescheduleTask => "reschedule_task",
+            Action::MoveTask => "move_task",
+            Action::SetPriority => "set_priority",
+            Action::AddLabel => "add_label",
+            Action::ToggleMark => "toggle_mark",
+            Action::SelectRange => "select_range",
+            Action::SelectAll => "select_all",
+            Action::ClearMarks => "clear_marks",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::Search => "search",
+            Action::CommandLine => "command_line",
+            Action::Palette => "palette",
+            Action::Calendar => "calendar",
+            Action::Agenda => "agenda",
+            Action::SyncNow => "sync_now",
+            Action::NextTheme => "next_theme",
+            Action::SwitchProfile => "switch_profile",
+            Action::ChangeApiKey => "change_api_key",
+            Action::Logout => "logout",
+            Action::Help => "help",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Quit => "Sync pending changes and quit",
+            Action::NextTask => "Select next task",
+            Action::PreviousTask => "Select previous task",
+            Action::FocusToday => "Focus the Today list",
+            Action::FocusInbox => "Focus the Inbox list",
+            Action::NextPane => "Focus the other list",
+            Action::ZoomPane => "Zoom the focused list or show all",
+            Action::HidePane => "Hide the focused list or show it again",
+            Action::FirstTask => "Select first task",
+            Action::LastTask => "Select last task",
+            Action::PageDown => "Scroll a page down",
+            Action::PageUp => "Scroll a page up",
+            Action::ToggleComplete => "Complete or reopen the selected task",
+            Action::DeleteTask => "Delete the selected task",
+            Action::EditTask => "Edit the selected task",
+            Action::RescheduleTask => "Reschedule the selected task",
+            Action::MoveTask => "Move the selected task to another project",
+            Action::SetPriority => "Set the priority of the selected task",
+            Action::AddLabel => "Add a label to the selected task",
+            Action::ToggleMark => "Mark or unmark the task and move down",
+            Action::SelectRange => "Start or end marking a range of tasks",
+            Action::SelectAll => "Mark every task in the focused list",
+            Action::ClearMarks => "Unmark all tasks",
+            Action::Undo => "Undo the last change",
+            Action::Redo => "Redo the last undone change",
+            Action::Search => "Search all tasks",
+            Action::CommandLine => "Enter a : command",
+            Action::Palette => "Open the command palette",
+            Action::Calendar => "Show tasks on a month calendar",
+            Action::Agenda => "Show timed tasks on a day and week timeline",
+            Action::SyncNow => "Sync changes and refresh tasks now",
+            Action::NextTheme => "Switch to the next color theme",
+            Action::SwitchProfile => "Switch to another profile or create one",
+            Action::ChangeApiKey => "Replace the saved API key",
+            Action::Logout => "Log out, removing the API key and cached tasks",
+            Action::Help => "Show or hide key bindings",
+        }
+    }
+
+    /// Actions shown in the footer while navigating the lists.
+    pub const HINTS: &'static [Action] = &[
+        Action::ToggleComplete,
+        Action::FocusToday,
+        Action::FocusInbox,
+        Action::Search,
+        Action::Palette,
+        Action::Help,
+        Action::Quit,
+    ];
+
+    /// Short label for the footer hints.
+    pub fn hint(&self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::NextTask => "down",
+            Action::PreviousTask => "up",
+            Action::FocusToday => "today",
+            Action::FocusInbox => "inbox",
+            Action::NextPane => "switch",
+            Action::ZoomPane => "zoom",
+            Action::HidePane => "hide",
+            Action::FirstTask => "first",
+            Action::LastTask => "last",
+            Action::PageDown => "page down",
+            Action::PageUp => "page up",
+            Action::ToggleComplete => "complete",
+            Action::DeleteTask => "delete",
+            Action::EditTask => "edit",
+            Action::RescheduleTask => "schedule",
+            Action::MoveTask => "move",
+            Action::SetPriority => "priority",
+            Action::AddLabel => "label",
+            Action::ToggleMark => "mark",
+            Action::SelectRange => "range",
+            Action::SelectAll => "all",
+            Action::ClearMarks => "unmark",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::Search => "search",
+            Action::CommandLine => "command",
+            Action::Palette => "palette",
+            Action::Calendar => "calendar",
+            Action::Agenda => "agenda",
+            Action::SyncNow => "sync",
+            Action::NextTheme => "theme",
+            Action::SwitchProfile => "profile",
+            Action::ChangeApiKey => "api key",
+            Action::Logout => "logout",
+            Action::Help => "help",
+        }
+    }
+
+    /// Whether a count prefix such as `5j` repeats the action. Completing
+    /// and deleting take the count as a number of tasks instead, see
+    /// [`App::counted_task_ids`](crate::app::App::counted_task_ids).
+    pub fn is_repeatable(&self) -> bool {
+        matches!(
+            self,
+            Action::NextTask
+                | Action::PreviousTask
+                | Action::PageDown
+                | Action::PageUp
+                | Action::ToggleMark
+        )
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL
+            .iter()
+            .copied()
+            .find(|action| action.name() == name)
+    }
+}
+
+/// A single key press, normalised so it can be compared against bindings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl Key {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        // Shift is already part of the character for printable keys
+        let modifiers = match code {
+            KeyCode::Char(_) => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+        Self { code, modifiers }
+    }
+
+    /// Parses keys such as `q`, `G`, `space`, `pagedown` or `ctrl-p`.
+    pub fn parse(text: &str) -> Result<Key, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = text;
+        // A trailing `-` is the minus key itself, not a separator
+        while let Some((modifier, key)) = rest.split_once('-').filter(|(_, key)| !key.is_empty()) {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" | "c" => KeyModifiers::CONTROL,
+                "alt" | "a" | "m" => KeyModifiers::ALT,
+                "shift" | "s" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier `{}` in `{}`", modifier, text)),
+            };
+            rest = key;
+        }
+
+        let code = match rest.to_lowercase().as_str() {
+            "space" => KeyCode::Char(' '),
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            lower => {
+                let mut chars = rest.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => match lower.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                        Some(n) => KeyCode::F(n),
+                        None => return Err(format!("unknown key `{}`", text)),
+                    },
+                }
+            }
+        };
+
+        // Terminals send shifted letters as the uppercase character, and
+        // which character other shifted keys produce depends on the layout
+        let code = match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => {
+                if !c.is_alphabetic() {
+                    return Err(format!(
+                        "`shift-` only combines with letters in `{}`, use the shifted key itself",
+                        text
+                    ));
+                }
+                KeyCode::Char(c.to_uppercase().next().unwrap_or(c))
+            }
+            code => code,
+        };
+
+        Ok(Key::new(code, modifiers))
+    }
+}
+
+impl From<KeyEvent> for Key {
+    fn from(event: KeyEvent) -> Self {
+        Key::new(event.code, event.modifiers)
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            KeyCode::Enter => write!(f, "enter"),
+            KeyCode::Esc => write!(f, "esc"),
+            KeyCode::Tab => write!(f, "tab"),
+            KeyCode::BackTab => write!(f, "backtab"),
+            KeyCode::Backspace => write!(f, "backspace"),
+            KeyCode::Delete => write!(f, "delete"),
+            KeyCode::Insert => write!(f, "insert"),
+            KeyCode::Up => write!(f, "up"),
+            KeyCode::Down => write!(f, "down"),
+            KeyCode::Left => write!(f, "left"),
+            KeyCode::Right => write!(f, "right"),
+            KeyCode::Home => write!(f, "home"),
+            KeyCode::End => write!(f, "end"),
+            KeyCode::PageUp => write!(f, "pageup"),
+            KeyCode::PageDown => write!(f, "pagedown"),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+/// One or more keys pressed in order, e.g. `g g`.
+pub type KeySequence = Vec<Key>;
+
+pub fn parse_sequence(text: &str) -> Result<KeySequence, String> {
+    let keys = text
+        .split_whitespace()
+        .map(Key::parse)
+        .collect::<Result<KeySequence, String>>()?;
+    if keys.is_empty() {
+        return Err("empty key binding".to_string());
+    }
+    Ok(keys)
+}
+
+pub fn format_sequence(keys: &[Key]) -> String {
+    keys.iter()
+        .map(Key::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Where key presses are routed.
+///
+/// The lists use the configurable [`Keymap`], every other mode has a fixed
+/// table of [`FormKey`]s; both drive the handler as well as the help overlay
+/// and the footer hints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Onboarding,
+    List,
+    Search,
+    CommandLine,
+    Palette,
+    Prompt,
+    Calendar,
+    Agenda,
+    Help,
+}
+
+impl Mode {
+    pub fn title(&self) -> &'static str {
+        match self {
+            Mode::Onboarding => "Onboarding",
+            Mode::List => "Lists",
+            Mode::Search => "Search",
+            Mode::CommandLine => "Command line",
+            Mode::Palette => "Command palette",
+            Mode::Prompt => "Task prompt",
+            Mode::Calendar => "Calendar",
+            Mode::Agenda => "Agenda",
+            Mode::Help => "Help",
+        }
+    }
+
+    /// Fixed keys of the mode; empty for the keymap-driven lists.
+    pub fn keys(&self) -> &'static [FormKey] {
+        match self {
+            Mode::Onboarding => ONBOARDING_KEYS,
+            Mode::List => &[],
+            Mode::Search => SEARCH_KEYS,
+            Mode::CommandLine => COMMAND_LINE_KEYS,
+            Mode::Palette => PALETTE_KEYS,
+            Mode::Prompt => PROMPT_KEYS,
+            Mode::Calendar => CALENDAR_KEYS,
+            Mode::Agenda => AGENDA_KEYS,
+            Mode::Help => HELP_KEYS,
+        }
+    }
+
+    pub fn lookup(&self, key: Key) -> Option<FormAction> {
+        self.keys()
+            .iter()
+            .find(|form_key| form_key.key == key)
+            .map(|form_key| form_key.action)
+    }
+
+    /// Footer hints as `(keys, label)`, with keys sharing a label merged,
+    /// e.g. `up/down move`.
+    pub fn hints(&self, keymap: &Keymap) -> Vec<(String, &'static str)> {
+        let entries: Vec<(String, &'static str)> = match self {
+            Mode::List => Action::HINTS
+                .iter()
+                .filter_map(|action| {
+                    let keys = keymap.keys_for(*action);
+                    keys.first().map(|keys| (keys.clone(), action.hint()))
+                })
+                .collect(),
+            _ => self
+                .keys()
+                .iter()
+                .map(|form_key| (form_key.key.to_string(), form_key.hint))
+                .collect(),
+        };
+        merge_hints(entries)
+    }
+}
+
+/// Merges hints sharing a label, e.g. `up` and `down` for `move`.
+pub fn merge_hints(entries: Vec<(String, &'static str)>) -> Vec<(String, &'static str)> {
+    let mut hints: Vec<(String, &'static str)> = Vec::new();
+    for (keys, hint) in entries {
+        match hints.iter_mut().find(|(_, existing)| *existing == hint) {
+            Some((existing, _)) => {
+                existing.push('/');
+                existing.push_str(&keys);
+            }
+            None => hints.push((keys, hint)),
+        }
+    }
+    hints
+}
+
+/// What a fixed key does in a text field or overlay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormAction {
+    Submit,
+    Cancel,
+    Next,
+    Previous,
+    DeleteChar,
+    ToggleReveal,
+    NextDay,
+    PreviousDay,
+    NextWeek,
+    PreviousWeek,
+    NextMonth,
+    PreviousMonth,
+    Today,
+}
+
+#[derive(Debug)]
+pub struct FormKey {
+    pub key: Key,
+    pub action: FormAction,
+    pub hint: &'static str,
+}
+
+const fn form_key(code: KeyCode, action: FormAction, hint: &'static str) -> FormKey {
+    FormKey {
+        key: Key {
+            code,
+            modifiers: KeyModifiers::NONE,
+        },
+        action,
+        hint,
+    }
+}
+
+const fn ctrl_key(c: char, action: FormAction, hint: &'static str) -> FormKey {
+    FormKey {
+        key: Key {
+            code: KeyCode::Char(c),
+            modifiers: KeyModifiers::CONTROL,
+        },
+        action,
+        hint,
+    }
+}
+
+const ONBOARDING_KEYS: &[FormKey] = &[
+    form_key(KeyCode::Enter, FormAction::Submit, "save key"),
+    form_key(KeyCode::Backspace, FormAction::DeleteChar, "delete"),
+    ctrl_key('r', FormAction::ToggleReveal, "show/hide"),
+    form_key(KeyCode::Esc, FormAction::Cancel, "quit"),
+    ctrl_key('c', FormAction::Cancel, "quit"),
+];
+
+const SEARCH_KEYS: &[FormKey] = &[
+    form_key(KeyCode::Enter, FormAction::Submit, "jump to task"),
+    form_key(KeyCode::Up, FormAction::Previous, "move"),
+    form_key(KeyCode::Down, FormAction::Next, "move"),
+    form_key(KeyCode::Backspace, FormAction::DeleteChar, "delete"),
+    form_key(KeyCode::Esc, FormAction::Cancel, "close"),
+];
+
+const COMMAND_LINE_KEYS: &[FormKey] = &[
+    form_key(KeyCode::Enter, FormAction::Submit, "run"),
+    form_key(KeyCode::Backspace, FormAction::DeleteChar, "delete"),
+    form_key(KeyCode::Esc, FormAction::Cancel, "cancel"),
+];
+
+const PALETTE_KEYS: &[FormKey] = &[
+    form_key(KeyCode::Enter, FormAction::Submit, "run action"),
+    form_key(KeyCode::Up, FormAction::Previous, "move"),
+    form_key(KeyCode::Down, FormAction::Next, "move"),
+    ctrl_key('p', FormAction::Previous, "move"),
+    ctrl_key('n', FormAction::Next, "move"),
+    form_key(KeyCode::Backspace, FormAction::DeleteChar, "delete"),
+    form_key(KeyCode::Esc, FormAction::Cancel, "close"),
+];
+
+const PROMPT_KEYS: &[FormKey] = &[
+    form_key(KeyCode::Enter, FormAction::Submit, "apply"),
+    form_key(KeyCode::Backspace, FormAction::DeleteChar, "delete"),
+    form_key(KeyCode::Esc, FormAction::Cancel, "cancel"),
+];
+
+const CALENDAR_KEYS: &[FormKey] = &[
+    form_key(KeyCode::Left, FormAction::PreviousDay, "day"),
+    form_key(KeyCode::Right, FormAction::NextDay, "day"),
+    form_key(KeyCode::Up, FormAction::PreviousWeek, "week"),
+    form_key(KeyCode::Down, FormAction::NextWeek, "week"),
+    form_key(KeyCode::PageUp, FormAction::PreviousMonth, "month"),
+    form_key(KeyCode::PageDown, FormAction::NextMonth, "month"),
+    form_key(KeyCode::Char('<'), FormAction::PreviousMonth, "month"),
+    form_key(KeyCode::Char('>'), FormAction::NextMonth, "month"),
+    form_key(KeyCode::Char('t'), FormAction::Today, "today"),
+    form_key(KeyCode::Char('k'), FormAction::Previous, "task"),
+    form_key(KeyCode::Char('j'), FormAction::Next, "task"),
+    form_key(KeyCode::Enter, FormAction::Submit, "pick up/drop task"),
+    form_key(KeyCode::Esc, FormAction::Cancel, "close"),
+];
+
+const AGENDA_KEYS: &[FormKey] = &[
+    form_key(KeyCode::Left, FormAction::PreviousDay, "day"),
+    form_key(KeyCode::Right, FormAction::NextDay, "day"),
+    form_key(KeyCode::Char('<'), FormAction::PreviousWeek, "week"),
+    form_key(KeyCode::Char('>'), FormAction::NextWeek, "week"),
+    form_key(KeyCode::Up, FormAction::Previous, "scroll"),
+    form_key(KeyCode::Down, FormAction::Next, "scroll"),
+    form_key(KeyCode::Char('k'), FormAction::Previous, "scroll"),
+    form_key(KeyCode::Char('j'), FormAction::Next, "scroll"),
+    form_key(KeyCode::Char('t'), FormAction::Today, "today"),
+    form_key(KeyCode::Esc, FormAction::Cancel, "close"),
+];
+
+const HELP_KEYS: &[FormKey] = &[form_key(KeyCode::Esc, FormAction::Cancel, "close")];
+
+/// Outcome of looking up the keys pressed so far.
+#[derive(Debug, PartialEq)]
+pub enum KeyMatch {
+    Action(Action),
+    /// The keys start a longer binding, wait for more input.
+    Prefix,
+    None,
+}
+
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<(KeySequence, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let defaults: &[(&str, Action)] = &[
+            ("q", Action::Quit),
+            ("ctrl-c", Action::Quit),
+            ("down", Action::NextTask),
+            ("up", Action::PreviousTask),
+            ("pagedown", Action::PageDown),
+            ("pageup", Action::PageUp),
+            ("home", Action::FirstTask),
+            ("end", Action::LastTask),
+            ("left", Action::FocusToday),
+            ("right", Action::FocusInbox),
+            ("tab", Action::NextPane),
+            ("z", Action::ZoomPane),
+            ("H", Action::HidePane),
+            ("space", Action::ToggleComplete),
+            ("e", Action::EditTask),
+            ("t", Action::RescheduleTask),
+            ("m", Action::MoveTask),
+            ("p", Action::SetPriority),
+            ("@", Action::AddLabel),
+            ("v", Action::ToggleMark),
+            ("V", Action::SelectRange),
+            ("ctrl-a", Action::SelectAll),
+            ("esc", Action::ClearMarks),
+            ("u", Action::Undo),
+            ("ctrl-r", Action::Redo),
+            ("/", Action::Search),
+            ("?", Action::Help),
+            ("ctrl-p", Action::Palette),
+            (":", Action::Palette),
+            ("r", Action::SyncNow),
+            ("T", Action::NextTheme),
+            ("P", Action::SwitchProfile),
+            ("c", Action::Calendar),
+            ("a", Action::Agenda),
+        ];
+        Self::from_table(defaults)
+    }
+}
+
+impl Keymap {
+    fn from_table(table: &[(&str, Action)]) -> Self {
+        Self {
+            bindings: table
+                .iter()
+                .map(|(keys, action)| (parse_sequence(keys).unwrap(), *action))
+                .collect(),
+        }
+    }
+
+    /// The default keymap extended with vim motions and commands.
+    ///
+    /// Vim bindings win over defaults using the same keys, so `:` opens the
+    /// command line instead of the palette.
+    pub fn vim() -> Self {
+        let vim = Self::from_table(&[
+            ("j", Action::NextTask),
+            ("k", Action::PreviousTask),
+            ("h", Action::FocusToday),
+            ("l", Action::FocusInbox),
+            ("g g", Action::FirstTask),
+            ("G", Action::LastTask),
+            ("ctrl-f", Action::PageDown),
+            ("ctrl-b", Action::PageUp),
+            ("x", Action::ToggleComplete),
+            ("d d", Action::DeleteTask),
+            (":", Action::CommandLine),
+        ]);
+        let mut keymap = Keymap::default();
+        keymap
+            .bindings
+            .retain(|(keys, _)| !vim.bindings.iter().any(|(vim_keys, _)| vim_keys == keys));
+        keymap.bindings.extend(vim.bindings);
+        keymap
+    }
+
+    /// Applies user overrides on top of this keymap.
+    ///
+    /// Overrides map an action name to the full list of bindings for that
+    /// action, replacing its defaults. An empty list unbinds the action.
+    pub fn with_overrides(
+        mut self,
+        overrides: &HashMap<String, Vec<String>>,
+    ) -> Result<Keymap, String> {
+        let mut names: Vec<&String> = overrides.keys().collect();
+        names.sort();
+
+        for name in names {
+            let action = Action::from_name(name)
+                .ok_or_else(|| format!("unknown action `{}` in keymap", name))?;
+            self.bindings.retain(|(_, bound)| *bound != action);
+            for binding in &overrides[name] {
+                let keys = parse_sequence(binding)
+                    .map_err(|e| format!("invalid binding for `{}`: {}", name, e))?;
+                self.bindings.push((keys, action));
+            }
+        }
+
+        self.check_conflicts()?;
+        Ok(self)
+    }
+
+    /// Rejects keymaps where one sequence is bound twice or is the prefix of
+    /// another, since the longer binding could never be reached.
+    pub fn check_conflicts(&self) -> Result<(), String> {
+        let mut conflicts = Vec::new();
+        for (i, (keys, action)) in self.bindings.iter().enumerate() {
+            for (other_keys, other_action) in &self.bindings[i + 1..] {
+                let (short, long) = if keys.len() <= other_keys.len() {
+                    (keys, other_keys)
+                } else {
+                    (other_keys, keys)
+                };
+                if long.starts_with(short)
+                    && (keys.len() != other_keys.len() || action != other_action)
+                {
+                    conflicts.push(format!(
+                        "`{}` ({}) conflicts with `{}` ({})",
+                        format_sequence(keys),
+                        action.name(),
+                        format_sequence(other_keys),
+                        other_action.name()
+                    ));
+                }
+            }
+        }
+
+        if conflicts.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("keymap conflicts: {}", conflicts.join("; ")))
+        }
+    }
+
+    pub fn lookup(&self, keys: &[Key]) -> KeyMatch {
+        let mut result = KeyMatch::None;
+        for (bound, action) in &self.bindings {
+            if bound.as_slice() == keys {
+                return KeyMatch::Action(*action);
+            }
+            if bound.starts_with(keys) {
+                result = KeyMatch::Prefix;
+            }
+        }
+        result
+    }
+
+    /// Key sequences bound to an action, in binding order.
+    pub fn keys_for(&self, action: Action) -> Vec<String> {
+        self.bindings
+            .iter()
+            .filter(|(_, bound)| *bound == action)
+            .map(|(keys, _)| format_sequence(keys))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(text: &str) -> Vec<Key> {
+        parse_sequence(text).unwrap()
+    }
+
+    #[test]
+    fn test_parse_keys() {
+        assert_eq!(
+            Key::parse("ctrl-p").unwrap(),
+            Key::new(KeyCode::Char('p'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(Key::parse("G").unwrap().code, KeyCode::Char('G'));
+        assert_eq!(Key::parse("-").unwrap().code, KeyCode::Char('-'));
+        assert_eq!(Key::parse("pagedown").unwrap().code, KeyCode::PageDown);
+        assert!(Key::parse("hyper-x").is_err());
+        assert_eq!(Key::parse("shift-a").unwrap(), Key::parse("A").unwrap());
+        assert_eq!(
+            Key::parse("ctrl-shift-a").unwrap(),
+            Key::new(KeyCode::Char('A'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            Key::parse("shift-a").unwrap(),
+            Key::from(KeyEvent::new(KeyCode::Char('A'), KeyModifiers::SHIFT))
+        );
+        assert!(Key::parse("shift-1").is_err());
+        assert_eq!(format_sequence(&press("g  g")), "g g");
+    }
+
+    #[test]
+    fn test_overrides_and_chords() {
+        let overrides = HashMap::from([
+            (
+                "next_task".to_string(),
+                vec!["j".to_string(), "down".to_string()],
+            ),
+            ("previous_task".to_string(), vec!["g k".to_string()]),
+        ]);
+        let keymap = Keymap::default().with_overrides(&overrides).unwrap();
+
+        assert_eq!(
+            keymap.lookup(&press("j")),
+            KeyMatch::Action(Action::NextTask)
+        );
+        assert_eq!(keymap.lookup(&press("g")), KeyMatch::Prefix);
+        assert_eq!(
+            keymap.lookup(&press("g k")),
+            KeyMatch::Action(Action::PreviousTask)
+        );
+        assert_eq!(keymap.lookup(&press("up")), KeyMatch::None);
+    }
+
+    #[test]
+    fn test_vim_keymap() {
+        let keymap = Keymap::vim();
+        assert!(keymap.check_conflicts().is_ok());
+        assert_eq!(keymap.lookup(&press("d")), KeyMatch::Prefix);
+        assert_eq!(
+            keymap.lookup(&press("d d")),
+            KeyMatch::Action(Action::DeleteTask)
+        );
+        assert_eq!(
+            keymap.lookup(&press("G")),
+            KeyMatch::Action(Action::LastTask)
+        );
+        assert_eq!(
+            keymap.lookup(&press("space")),
+            KeyMatch::Action(Action::ToggleComplete)
+        );
+        assert_eq!(
+            keymap.lookup(&press(":")),
+            KeyMatch::Action(Action::CommandLine)
+        );
+    }
+
+    #[test]
+    fn test_mode_hints() {
+        let keymap = Keymap::default();
+        let hints = Mode::Search.hints(&keymap);
+        assert!(hints.contains(&("up/down".to_string(), "move")));
+        assert_eq!(
+            Mode::Search.lookup(Key::parse("esc").unwrap()),
+            Some(FormAction::Cancel)
+        );
+
+        // List hints follow the active keymap
+        let overrides = HashMap::from([("search".to_string(), vec!["ctrl-f".to_string()])]);
+        let keymap = Keymap::default().with_overrides(&overrides).unwrap();
+        assert!(Mode::List
+            .hints(&keymap)
+            .contains(&("ctrl-f".to_string(), "search")));
+    }
+
+    #[test]
+    fn test_conflicts_are_rejected() {
+        let overrides = HashMap::from([("next_task".to_string(), vec!["q".to_string()])]);
+        assert!(Keymap::default().with_overrides(&overrides).is_err());
+
+        let overrides = HashMap::from([("next_task".to_string(), vec!["q q".to_string()])]);
+        assert!(Keymap::default().with_overrides(&overrides).is_err());
+
+        let overrides = HashMap::from([("launch".to_string(), vec!["l".to_string()])]);
+        assert!(Keymap::default().with_overrides(&overrides).is_err());
+    }
+}
@@ -0,0 +1,265 @@
+use ratatui::layout::{Constraint, Layout, Position, Rect};
+use serde::{Deserialize, Serialize};
+
+/// The task lists shown side by side, stacked or as tabs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pane {
+    Today,
+    Inbox,
+}
+
+impl Pane {
+    pub fn title(&self) -> &'static str {
+        match self {
+            Pane::Today => "Today",
+            Pane::Inbox => "Inbox",
+        }
+    }
+
+    pub fn other(&self) -> Pane {
+        match self {
+            Pane::Today => Pane::Inbox,
+            Pane::Inbox => Pane::Today,
+        }
+    }
+}
+
+/// How the panes share the screen, picked from the terminal size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutMode {
+    /// Side by side.
+    Split,
+    /// One above the other, for terminals taller than they are wide.
+    Stacked,
+    /// One pane at a time with a tab bar, for narrow terminals.
+    Tabs,
+}
+
+/// Pane preferences from the settings file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LayoutConfig {
+    /// Share of the screen for the Today pane, in percent.
+    pub today_ratio: u16,
+    /// Below this many columns only one pane is shown at a time.
+    pub narrow_width: u16,
+    /// Stack the panes when the terminal is taller than it is wide.
+    pub stack_when_tall: bool,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        Self {
+            today_ratio: 70,
+            narrow_width: 60,
+            stack_when_tall: true,
+        }
+    }
+}
+
+impl LayoutConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(10..=90).contains(&self.today_ratio) {
+            return Err(format!(
+                "`layout.today_ratio` must be between 10 and 90, got {}",
+                self.today_ratio
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn mode(&self, width: u16, height: u16) -> LayoutMode {
+        if width < self.narrow_width {
+            return LayoutMode::Tabs;
+        }
+        // Cells are about twice as tall as they are wide
+        if self.stack_when_tall && height.saturating_mul(2) > width {
+            LayoutMode::Stacked
+        } else {
+            LayoutMode::Split
+        }
+    }
+}
+
+/// Panes hidden or zoomed with a key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PaneState {
+    /// Show only the focused pane.
+    pub zoomed: bool,
+    pub hidden: Option<Pane>,
+}
+
+impl PaneState {
+    /// Panes to draw, given the focused one.
+    pub fn visible(&self, mode: LayoutMode, focused: Pane) -> Vec<Pane> {
+        if self.zoomed || mode == LayoutMode::Tabs {
+            vec![focused]
+        } else {
+            [Pane::Today, Pane::Inbox]
+                .into_iter()
+                .filter(|pane| self.hidden != Some(*pane))
+                .collect()
+        }
+    }
+}
+
+/// Where the panes and, in tab mode, the tab bar go within `area`.
+pub fn pane_areas(
+    config: &LayoutConfig,
+    state: &PaneState,
+    mode: LayoutMode,
+    focused: Pane,
+    area: Rect,
+) -> (Option<Rect>, Vec<(Pane, Rect)>) {
+    let (tabs, area) = if mode == LayoutMode::Tabs {
+        let [tabs, rest] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(area);
+        (Some(tabs), rest)
+    } else {
+        (None, area)
+    };
+
+    let panes = match state.visible(mode, focused).as_slice() {
+        [pane] => vec![(*pane, area)],
+        _ => {
+            let constraints = [
+                Constraint::Percentage(config.today_ratio),
+                Constraint::Percentage(100 - config.today_ratio),
+            ];
+            let [today, inbox] = if mode == LayoutMode::Stacked {
+                Layout::vertical(constraints).areas(area)
+            } else {
+                Layout::horizontal(constraints).areas(area)
+            };
+            vec![(Pane::Today, today), (Pane::Inbox, inbox)]
+        }
+    };
+    (tabs, panes)
+}
+
+/// A list as last drawn, to map mouse positions back to tasks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListArea {
+    pub pane: Pane,
+    /// The whole pane including its border.
+    pub area: Rect,
+    /// The rows inside the border.
+    pub rows: Rect,
+    /// Index of the task in the first visible row.
+    pub offset: usize,
+    /// Column of the ☐ glyph.
+    pub checkbox_x: u16,
+}
+
+/// Where the last frame put things the mouse can click.
+#[derive(Debug, Clone, Default)]
+pub struct RenderedAreas {
+    pub lists: Vec<ListArea>,
+    pub tabs: Vec<(Pane, Rect)>,
+}
+
+/// What is under the mouse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hit {
+    Tab(Pane),
+    /// A pane outside its rows, e.g. its border.
+    Pane(Pane),
+    Row {
+        pane: Pane,
+        /// Index in the pane's task list; may be past its end.
+        index: usize,
+        checkbox: bool,
+    },
+}
+
+impl Hit {
+    pub fn pane(&self) -> Pane {
+        match self {
+            Hit::Tab(pane) | Hit::Pane(pane) | Hit::Row { pane, .. } => *pane,
+        }
+    }
+}
+
+impl RenderedAreas {
+    pub fn hit(&self, column: u16, row: u16) -> Option<Hit> {
+        let position = Position::new(column, row);
+        if let Some((pane, _)) = self.tabs.iter().find(|(_, area)| area.contains(position)) {
+            return Some(Hit::Tab(*pane));
+        }
+        let list = self
+            .lists
+            .iter()
+            .find(|list| list.area.contains(position))?;
+        if !list.rows.contains(position) {
+            return Some(Hit::Pane(list.pane));
+        }
+        Some(Hit::Row {
+            pane: list.pane,
+            index: list.offset + (row - list.rows.y) as usize,
+            checkbox: column == list.checkbox_x,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breakpoints_and_pane_areas() {
+        let config = LayoutConfig::default();
+        assert_eq!(config.mode(50, 40), LayoutMode::Tabs);
+        assert_eq!(config.mode(100, 60), LayoutMode::Stacked);
+        assert_eq!(config.mode(160, 40), LayoutMode::Split);
+
+        let area = Rect::new(0, 0, 100, 30);
+        let state = PaneState::default();
+        let (tabs, panes) = pane_areas(&config, &state, LayoutMode::Split, Pane::Today, area);
+        assert!(tabs.is_none());
+        assert_eq!(panes[0].1.width, 70);
+
+        // Tabs and zoom show only the focused pane
+        let (tabs, panes) = pane_areas(&config, &state, LayoutMode::Tabs, Pane::Inbox, area);
+        assert_eq!(tabs.unwrap().height, 1);
+        assert_eq!(panes, vec![(Pane::Inbox, Rect::new(0, 1, 100, 29))]);
+
+        let hidden = PaneState {
+            zoomed: false,
+            hidden: Some(Pane::Today),
+        };
+        let (_, panes) = pane_areas(&config, &hidden, LayoutMode::Split, Pane::Inbox, area);
+        assert_eq!(panes, vec![(Pane::Inbox, area)]);
+
+        let invalid = LayoutConfig {
+            today_ratio: 95,
+            ..Default::default()
+        };
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_hit_testing() {
+        let rendered = RenderedAreas {
+            lists: vec![ListArea {
+                pane: Pane::Inbox,
+                area: Rect::new(10, 1, 20, 10),
+                rows: Rect::new(11, 2, 18, 8),
+                offset: 5,
+                checkbox_x: 16,
+            }],
+            tabs: vec![(Pane::Today, Rect::new(0, 0, 7, 1))],
+        };
+
+        assert_eq!(rendered.hit(3, 0), Some(Hit::Tab(Pane::Today)));
+        assert_eq!(rendered.hit(10, 4), Some(Hit::Pane(Pane::Inbox)));
+        assert_eq!(
+            rendered.hit(16, 3),
+            Some(Hit::Row {
+                pane: Pane::Inbox,
+                index: 6,
+                checkbox: true
+            })
+        );
+        assert_eq!(rendered.hit(50, 3), None);
+    }
+}
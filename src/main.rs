@@ -1,29 +1,102 @@
-use crate::todoist::PendingChange::TaskCompletion;
-use crate::todoist::{PendingChange, TodoistClient};
 use crate::{
     app::{App, AppResult},
     event::{Event, EventHandler},
-    handler::handle_key_events,
+    handler::{handle_key_events, handle_mouse_events, handle_paste_events},
+    profile::{Profile, DEFAULT_PROFILE},
     tui::Tui,
 };
+use crossterm::event::{self as terminal_event, KeyCode, KeyEventKind, KeyModifiers};
 use ratatui::{backend::CrosstermBackend, Terminal};
-use std::io;
+use std::io::{self, Write};
 use std::sync::Arc;
-use std::time::Duration;
-use tokio::sync::{mpsc, Mutex};
+use tokio::sync::Mutex;
 
+pub mod agenda;
 pub mod app;
+pub mod calendar;
 pub mod config;
 pub mod event;
 pub mod handler;
+pub mod input;
+pub mod keymap;
+pub mod layout;
+pub mod palette;
+pub mod profile;
+pub mod prompt;
+pub mod search;
+pub mod settings;
+pub mod sync;
+pub mod theme;
+pub mod toast;
 pub mod todoist;
 pub mod tui;
 pub mod ui;
 
 #[tokio::main]
 async fn main() -> AppResult<()> {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let profile = match args.iter().position(|arg| arg == "--profile") {
+        Some(i) if i + 1 < args.len() => {
+            args.remove(i);
+            args.remove(i)
+        }
+        Some(_) => usage(),
+        None => DEFAULT_PROFILE.to_string(),
+    };
+
+    // Upgrade the config of older versions, or refuse it if a newer one
+    // wrote it, before anything reads or overwrites it
+    let profile = Profile::open(&profile)?;
+    profile.key_manager().check_config()?;
+
+    // Commands managing saved keys run before the app loads and uses them
+    match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["keys"] => {
+            let keys = profile.key_manager().list_keys()?;
+            if keys.is_empty() {
+                println!("No saved API keys in profile {}", profile.name);
+            }
+            for key in keys {
+                println!("{}", key.summary());
+            }
+            return Ok(());
+        }
+        ["logout"] => {
+            profile.key_manager().remove_api_key("todoist")?;
+            profile.clear_cache()?;
+            println!("Logged out of profile {}", profile.name);
+            return Ok(());
+        }
+        _ => {}
+    }
+
     // Create an application.
-    let application = App::new();
+    let mut application = App::new(profile);
+    match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        [] => {}
+        // Unlock before starting, keeping the vault key in memory only
+        ["unlock"] => application.unlock(&read_passphrase("Passphrase: ")?)?,
+        ["vault", "enable"] => {
+            let passphrase = read_passphrase("New passphrase: ")?;
+            if read_passphrase("Repeat passphrase: ")? != passphrase {
+                return Err("Passphrases do not match".into());
+            }
+            application.api_key_manager.enable_vault(&passphrase)?;
+            println!("Vault enabled, run `todoclist unlock` or unlock on start");
+            return Ok(());
+        }
+        ["vault", "disable"] => {
+            application.unlock(&read_passphrase("Passphrase: ")?)?;
+            application.api_key_manager.disable_vault()?;
+            println!("Vault disabled");
+            return Ok(());
+        }
+        _ => usage(),
+    }
+    application.load_settings()?;
+    let (width, height) = crossterm::terminal::size()?;
+    application.resize(width, height);
+    application.open_default_view();
     let app = Arc::new(Mutex::new(application));
 
     // Initialize the terminal user interface.
@@ -33,60 +106,7 @@ async fn main() -> AppResult<()> {
     let mut tui = Tui::new(terminal, events);
     tui.init()?;
 
-    let async_app = Arc::clone(&app);
-    if async_app.lock().await.api_key.is_some() {
-        let (tx, mut rx) = mpsc::channel(32);
-        let refresh_interval = async_app.lock().await.refresh_interval;
-        let key = async_app.lock().await.api_key.clone().unwrap();
-
-        tokio::spawn(async move {
-            let client = TodoistClient::new(key); // Remove Arc wrapper
-            let mut interval = tokio::time::interval(Duration::from_secs(refresh_interval));
-
-            loop {
-                interval.tick().await;
-                match client.get_tasks(None).await {
-                    Ok(tasks) => {
-                        if let Err(_) = tx.send(tasks).await {
-                            break;
-                        }
-                    }
-                    Err(e) => eprintln!("Error fetching tasks: {}", e),
-                }
-            }
-        });
-
-        // Spawn the task processing
-        let process_app = Arc::clone(&app);
-        tokio::spawn(async move {
-            while let Some(tasks) = rx.recv().await {
-                let mut app = process_app.lock().await;
-                app.tasks = tasks;
-            }
-        });
-
-        let process_app = Arc::clone(&app);
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(Duration::from_secs(2));
-            loop {
-                // First tick completes immediately
-                interval.tick().await;
-                interval.tick().await;
-                let mut ps = process_app.lock().await;
-                let changes: Vec<PendingChange> = ps.pending_changes.iter().cloned().collect();
-                ps.pending_changes = vec![];
-                for change in &changes {
-                    match change {
-                        TaskCompletion { task_id, completed } => {
-                            if let Some(client) = &ps.todoist_client {
-                                let _ = client.update_task_completion(&task_id, *completed).await;
-                            }
-                        }
-                    }
-                }
-            }
-        });
-    }
+    sync::spawn(Arc::clone(&app));
 
     // Start the main loop.
     while app.lock().await.running {
@@ -94,10 +114,13 @@ async fn main() -> AppResult<()> {
         tui.draw(&mut *app.lock().await)?;
         // Handle events.
         match tui.events.next().await? {
-            Event::Tick => {}
+            Event::Tick => app.lock().await.tick().await,
             Event::Key(key_event) => handle_key_events(key_event, &mut *app.lock().await).await,
-            Event::Mouse(_) => {}
-            Event::Resize(_, _) => {}
+            Event::Mouse(mouse_event) => {
+                handle_mouse_events(mouse_event, &mut *app.lock().await)
+            }
+            Event::Resize(width, height) => app.lock().await.resize(width, height),
+            Event::Paste(text) => handle_paste_events(&text, &mut *app.lock().await),
         }
     }
 
@@ -105,3 +128,39 @@ async fn main() -> AppResult<()> {
     tui.exit()?;
     Ok(())
 }
+
+fn usage() -> ! {
+    eprintln!("Usage: todoclist [--profile <name>] [COMMAND]");
+    eprintln!("Commands: unlock, vault enable, vault disable, keys, logout");
+    std::process::exit(2);
+}
+
+/// Reads a line from the terminal without echoing it.
+fn read_passphrase(prompt: &str) -> io::Result<String> {
+    eprint!("{}", prompt);
+    io::stderr().flush()?;
+    crossterm::terminal::enable_raw_mode()?;
+    let mut passphrase = String::new();
+    let result = loop {
+        match terminal_event::read() {
+            Ok(terminal_event::Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                match key.code {
+                    KeyCode::Enter => break Ok(()),
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        break Err(io::Error::new(io::ErrorKind::Interrupted, "Cancelled"));
+                    }
+                    KeyCode::Char(c) => passphrase.push(c),
+                    KeyCode::Backspace => {
+                        passphrase.pop();
+                    }
+                    _ => {}
+                }
+            }
+            Ok(_) => {}
+            Err(e) => break Err(e),
+        }
+    };
+    crossterm::terminal::disable_raw_mode()?;
+    eprintln!();
+    result.map(|()| passphrase)
+}
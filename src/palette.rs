@@ -0,0 +1,125 @@
+use crate::input::TextInput;
+use crate::keymap::Action;
+use crate::search::{fuzzy_match, FuzzyMatch};
+use ratatui::widgets::ListState;
+
+#[derive(Debug, Clone)]
+pub struct PaletteEntry {
+    pub action: Action,
+    /// Match against the action description, `None` while the query is empty.
+    pub matched: Option<FuzzyMatch>,
+}
+
+/// State of the command palette overlay.
+#[derive(Debug)]
+pub struct PaletteState {
+    pub query: TextInput,
+    pub entries: Vec<PaletteEntry>,
+    pub list_state: ListState,
+}
+
+impl Default for PaletteState {
+    fn default() -> Self {
+        let mut palette = Self {
+            query: TextInput::default(),
+            entries: Vec::new(),
+            list_state: ListState::default(),
+        };
+        palette.update();
+        palette
+    }
+}
+
+impl PaletteState {
+    /// Filters all actions by the query, best matches first.
+    pub fn update(&mut self) {
+        self.entries = if self.query.value().trim().is_empty() {
+            Action::ALL
+                .iter()
+                .map(|action| PaletteEntry {
+                    action: *action,
+                    matched: None,
+                })
+                .collect()
+        } else {
+            let mut entries: Vec<PaletteEntry> = Action::ALL
+                .iter()
+                .filter_map(|action| {
+                    // The config name, e.g. `sync_now`, also matches and
+                    // ranks actions matching on both higher
+                    let by_description = fuzzy_match(self.query.value(), action.description());
+                    let by_name = fuzzy_match(self.query.value(), action.name());
+                    if by_description.is_none() && by_name.is_none() {
+                        return None;
+                    }
+                    let score = by_description.as_ref().map_or(0, |m| m.score)
+                        + by_name.as_ref().map_or(0, |m| m.score);
+                    Some(PaletteEntry {
+                        action: *action,
+                        matched: Some(FuzzyMatch {
+                            score,
+                            indices: by_description.map(|m| m.indices).unwrap_or_default(),
+                        }),
+                    })
+                })
+                .collect();
+            entries.sort_by_key(|entry| {
+                std::cmp::Reverse(entry.matched.as_ref().map_or(0, |m| m.score))
+            });
+            entries
+        };
+        self.list_state.select(if self.entries.is_empty() {
+            None
+        } else {
+            Some(0)
+        });
+    }
+
+    pub fn selected(&self) -> Option<Action> {
+        self.list_state
+            .selected()
+            .and_then(|i| self.entries.get(i))
+            .map(|entry| entry.action)
+    }
+
+    pub fn next(&mut self) {
+        if self.entries.is_empty() {
+            return;
+        }
+        let i = match self.list_state.selected() {
+            Some(i) if i + 1 < self.entries.len() => i + 1,
+            _ => 0,
+        };
+        self.list_state.select(Some(i));
+    }
+
+    pub fn previous(&mut self) {
+        if self.entries.is_empty() {
+            return;
+        }
+        let i = match self.list_state.selected() {
+            Some(0) | None => self.entries.len() - 1,
+            Some(i) => i - 1,
+        };
+        self.list_state.select(Some(i));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_palette_filters_actions() {
+        let mut palette = PaletteState::default();
+        assert_eq!(palette.entries.len(), Action::ALL.len());
+
+        palette.query = TextInput::new("sync");
+        palette.update();
+        assert_eq!(palette.selected(), Some(Action::SyncNow));
+
+        palette.query = TextInput::new("zzz");
+        palette.update();
+        assert_eq!(palette.selected(), None);
+    }
+}
@@ -0,0 +1,196 @@
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::ApiKeyManager;
+use crate::settings::SETTINGS_FILE;
+use crate::todoist::{Project, Task};
+
+/// Profile used when none is given on the command line.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// A named account, e.g. `work` or `personal`, with its own API key,
+/// settings and cached tasks.
+///
+/// The default profile keeps its files directly in the config and cache
+/// directories, as before profiles existed; the others live in a
+/// `profiles/<name>` directory below them.
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub name: String,
+    config_base: PathBuf,
+    cache_base: PathBuf,
+}
+
+/// Tasks and projects of the last complete refresh, shown on start until
+/// the first refresh of the session arrives.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaskCache {
+    pub tasks: Vec<Task>,
+    pub projects: Vec<Project>,
+}
+
+impl Profile {
+    /// Opens a profile in the user's config and cache directories,
+    /// creating it on first use.
+    pub fn open(name: &str) -> Result<Profile, String> {
+        let proj_dirs = ProjectDirs::from("dev", "mmlak", "todoclist")
+            .ok_or("Could not determine config directory")?;
+        Self::in_dirs(name, proj_dirs.config_dir(), proj_dirs.cache_dir())
+    }
+
+    /// Opens a profile below the given config and cache directories.
+    pub fn in_dirs(name: &str, config_base: &Path, cache_base: &Path) -> Result<Profile, String> {
+        validate_name(name)?;
+        let profile = Profile {
+            name: name.to_string(),
+            config_base: config_base.to_path_buf(),
+            cache_base: cache_base.to_path_buf(),
+        };
+        fs::create_dir_all(profile.config_dir())
+            .map_err(|e| format!("Failed to create profile `{}`: {}", name, e))?;
+        Ok(profile)
+    }
+
+    /// Opens another profile next to this one.
+    pub fn sibling(&self, name: &str) -> Result<Profile, String> {
+        Self::in_dirs(name, &self.config_base, &self.cache_base)
+    }
+
+    fn config_dir(&self) -> PathBuf {
+        profile_dir(&self.config_base, &self.name)
+    }
+
+    fn cache_path(&self) -> PathBuf {
+        profile_dir(&self.cache_base, &self.name).join("tasks.json")
+    }
+
+    /// Key manager reading and writing this profile's config.
+    pub fn key_manager(&self) -> ApiKeyManager {
+        ApiKeyManager::in_dir(&self.config_dir())
+    }
+
+    /// The profile's hand-editable settings file.
+    pub fn settings_path(&self) -> PathBuf {
+        self.config_dir().join(SETTINGS_FILE)
+    }
+
+    /// Names of all profiles, the default one first.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(self.config_base.join("profiles"))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|name| validate_name(name).is_ok() && name != DEFAULT_PROFILE)
+            .collect();
+        names.sort();
+        names.insert(0, DEFAULT_PROFILE.to_string());
+        names
+    }
+
+    /// The cached tasks, if a refresh ever completed for this profile.
+    pub fn load_cache(&self) -> Option<TaskCache> {
+        let data = fs::read_to_string(self.cache_path()).ok()?;
+        serde_json::from_str(&data).ok()
+    }
+
+    /// Forgets the cached tasks, e.g. on logout.
+    pub fn clear_cache(&self) -> Result<(), String> {
+        match fs::remove_file(self.cache_path()) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(format!("Failed to remove cache: {}", e))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn save_cache(&self, tasks: &[Task], projects: &[Project]) -> Result<(), String> {
+        let path = self.cache_path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create cache directory: {}", e))?;
+        }
+        let cache = TaskCache {
+            tasks: tasks.to_vec(),
+            projects: projects.to_vec(),
+        };
+        let data = serde_json::to_string(&cache)
+            .map_err(|e| format!("Failed to serialize cache: {}", e))?;
+        fs::write(&path, data).map_err(|e| format!("Failed to write cache: {}", e))
+    }
+}
+
+fn profile_dir(base: &Path, name: &str) -> PathBuf {
+    if name == DEFAULT_PROFILE {
+        base.to_path_buf()
+    } else {
+        base.join("profiles").join(name)
+    }
+}
+
+/// Profile names become directory names, so only letters, digits, `-` and
+/// `_` are allowed.
+fn validate_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "invalid profile name `{}`, use letters, digits, `-` and `_`",
+            name
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles_are_isolated() {
+        let base = std::env::temp_dir().join(format!("todoclist-{}", uuid::Uuid::new_v4()));
+        let (config, cache) = (base.join("config"), base.join("cache"));
+        let default = Profile::in_dirs(DEFAULT_PROFILE, &config, &cache).unwrap();
+        let work = default.sibling("work").unwrap();
+        default.sibling("personal").unwrap();
+        assert!(default.sibling("../evil").is_err());
+        assert!(default.sibling("").is_err());
+
+        assert_eq!(default.names(), vec!["default", "personal", "work"]);
+
+        default
+            .key_manager()
+            .save_api_key("todoist", "home-key")
+            .unwrap();
+        work.key_manager()
+            .save_api_key("todoist", "work-key")
+            .unwrap();
+        assert_eq!(
+            default.key_manager().load_api_key("todoist").unwrap(),
+            "home-key"
+        );
+        assert_eq!(
+            work.key_manager().load_api_key("todoist").unwrap(),
+            "work-key"
+        );
+        assert!(config.join("config.json").exists());
+
+        let project = Project {
+            id: "1".to_string(),
+            name: "Work".to_string(),
+        };
+        assert!(work.load_cache().is_none());
+        work.save_cache(&[], std::slice::from_ref(&project))
+            .unwrap();
+        assert_eq!(work.load_cache().unwrap().projects[0].name, "Work");
+        assert!(default.load_cache().is_none());
+        work.clear_cache().unwrap();
+        assert!(work.load_cache().is_none());
+    }
+}
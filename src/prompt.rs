@@ -0,0 +1,102 @@
+use crate::input::TextInput;
+use crate::search::fuzzy_match;
+use crate::todoist::Project;
+
+/// What a [`Prompt`] asks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptKind {
+    EditContent,
+    Reschedule,
+    MoveToProject,
+    SetPriority,
+    AddLabel,
+    SwitchProfile,
+}
+
+impl PromptKind {
+    pub fn title(&self) -> &'static str {
+        match self {
+            PromptKind::EditContent => "Edit task",
+            PromptKind::Reschedule => "Reschedule",
+            PromptKind::MoveToProject => "Move to project",
+            PromptKind::SetPriority => "Set priority",
+            PromptKind::AddLabel => "Add label",
+            PromptKind::SwitchProfile => "Switch profile",
+        }
+    }
+
+    /// Shown while the input is empty.
+    pub fn placeholder(&self) -> &'static str {
+        match self {
+            PromptKind::EditContent => "Task content",
+            PromptKind::Reschedule => "e.g. tomorrow, friday, 2024-06-01, no date",
+            PromptKind::MoveToProject => "Project name",
+            PromptKind::SetPriority => "1 (urgent) to 4 (normal)",
+            PromptKind::AddLabel => "Label name",
+            PromptKind::SwitchProfile => "Profile name, e.g. work",
+        }
+    }
+}
+
+/// A one-line input whose value is applied to tasks on submit, or to the
+/// session for [`PromptKind::SwitchProfile`].
+#[derive(Debug)]
+pub struct Prompt {
+    pub kind: PromptKind,
+    /// Tasks the input applies to.
+    pub task_ids: Vec<String>,
+    pub input: TextInput,
+}
+
+impl Prompt {
+    pub fn new(kind: PromptKind, task_ids: Vec<String>, input: String) -> Self {
+        Self {
+            kind,
+            task_ids,
+            input: TextInput::new(input),
+        }
+    }
+}
+
+/// Parses a priority as shown to users, `p1` being the most urgent, into
+/// Todoist's API value where 4 is the most urgent.
+pub fn parse_priority(input: &str) -> Option<u8> {
+    let input = input.trim().to_lowercase();
+    let level: u8 = input.strip_prefix('p').unwrap_or(&input).parse().ok()?;
+    (1..=4).contains(&level).then(|| 5 - level)
+}
+
+/// The project best matching `query`, if any matches at all.
+pub fn match_project<'a>(query: &str, projects: &'a [Project]) -> Option<&'a Project> {
+    projects
+        .iter()
+        .filter_map(|project| fuzzy_match(query, &project.name).map(|m| (m.score, project)))
+        .max_by_key(|(score, _)| *score)
+        .map(|(_, project)| project)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_project() {
+        let project = |id: &str, name: &str| Project {
+            id: id.to_string(),
+            name: name.to_string(),
+        };
+        let projects = vec![project("1", "Inbox"), project("2", "Work Items")];
+
+        assert_eq!(match_project("work", &projects).unwrap().id, "2");
+        assert_eq!(match_project("inb", &projects).unwrap().id, "1");
+        assert!(match_project("xyz", &projects).is_none());
+    }
+
+    #[test]
+    fn test_parse_priority() {
+        assert_eq!(parse_priority("p1"), Some(4));
+        assert_eq!(parse_priority("4"), Some(1));
+        assert_eq!(parse_priority("5"), None);
+        assert_eq!(parse_priority("high"), None);
+    }
+}
@@ -0,0 +1,270 @@
+use crate::input::TextInput;
+use crate::todoist::{Project, Task};
+use ratatui::widgets::ListState;
+
+/// Result of matching a query against a piece of text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    /// Char indices (not byte offsets) of the matched characters in the text.
+    pub indices: Vec<usize>,
+}
+
+/// Matches `query` as a case-insensitive subsequence of `text`.
+///
+/// Consecutive matches and matches at the start of a word score higher,
+/// gaps between matched characters score lower.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<FuzzyMatch> {
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    if query.is_empty() {
+        return None;
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut indices = Vec::with_capacity(query.len());
+    let mut score = 0;
+    let mut q = 0;
+    let mut last_match: Option<usize> = None;
+
+    for (i, c) in chars.iter().enumerate() {
+        if q == query.len() {
+            break;
+        }
+        if !c.to_lowercase().eq(query[q].to_lowercase()) {
+            continue;
+        }
+
+        score += 1;
+        let word_start = i == 0 || !chars[i - 1].is_alphanumeric();
+        if word_start {
+            score += 8;
+        }
+        match last_match {
+            Some(last) if last + 1 == i => score += 5,
+            Some(last) => score -= (i - last - 1).min(5) as i64,
+            None => score -= i.min(10) as i64,
+        }
+
+        indices.push(i);
+        last_match = Some(i);
+        q += 1;
+    }
+
+    if q == query.len() {
+        Some(FuzzyMatch { score, indices })
+    } else {
+        None
+    }
+}
+
+/// Which part of a task a search result matched on.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MatchField {
+    Content,
+    Description,
+    Label(String),
+    Project(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub task_id: String,
+    pub field: MatchField,
+    pub matched: FuzzyMatch,
+}
+
+/// State of the `/` search overlay.
+#[derive(Debug, Default)]
+pub struct SearchState {
+    pub query: TextInput,
+    pub results: Vec<SearchResult>,
+    pub list_state: ListState,
+    /// The query `results` were found for.
+    searched: String,
+}
+
+impl SearchState {
+    /// Re-runs the query against all open tasks. A new query selects the
+    /// best match; otherwise the selection follows its task, or stays on its
+    /// row when the task is gone.
+    pub fn update(&mut self, tasks: &[Task], projects: &[Project]) {
+        let same_query = self.query.value() == self.searched;
+        let selected = self.selected().map(|result| result.task_id.clone());
+        let row = self.list_state.selected().unwrap_or(0);
+
+        self.results = search_tasks(self.query.value(), tasks, projects);
+        self.searched = self.query.value().to_string();
+        let last = match self.results.len() {
+            0 => {
+                self.list_state.select(None);
+                return;
+            }
+            len => len - 1,
+        };
+        let position = selected
+            .filter(|_| same_query)
+            .and_then(|id| self.results.iter().position(|result| result.task_id == id));
+        self.list_state.select(Some(match position {
+            Some(i) => i,
+            None if same_query => row.min(last),
+            None => 0,
+        }));
+    }
+
+    pub fn selected(&self) -> Option<&SearchResult> {
+        self.list_state.selected().and_then(|i| self.results.get(i))
+    }
+
+    pub fn next(&mut self) {
+        if self.results.is_empty() {
+            return;
+        }
+        let i = match self.list_state.selected() {
+            Some(i) if i + 1 < self.results.len() => i + 1,
+            _ => 0,
+        };
+        self.list_state.select(Some(i));
+    }
+
+    pub fn previous(&mut self) {
+        if self.results.is_empty() {
+            return;
+        }
+        let i = match self.list_state.selected() {
+            Some(0) | None => self.results.len() - 1,
+            Some(i) => i - 1,
+        };
+        self.list_state.select(Some(i));
+    }
+}
+
+/// Fuzzy-matches the query against content, description, labels and project
+/// name of every open task, keeping the best field per task.
+pub fn search_tasks(query: &str, tasks: &[Task], projects: &[Project]) -> Vec<SearchResult> {
+    let mut results: Vec<SearchResult> = tasks
+        .iter()
+        .filter(|task| !task.is_completed)
+        .filter_map(|task| {
+            let project = projects
+                .iter()
+                .find(|project| project.id == task.project_id)
+                .map(|project| project.name.clone());
+
+            let mut candidates = vec![
+                (MatchField::Content, task.content.clone()),
+                (MatchField::Description, task.description.clone()),
+            ];
+            candidates.extend(
+                task.labels
+                    .iter()
+                    .map(|label| (MatchField::Label(label.clone()), label.clone())),
+            );
+            candidates.extend(project.map(|name| (MatchField::Project(name.clone()), name)));
+
+            candidates
+                .into_iter()
+                .filter_map(|(field, text)| fuzzy_match(query, &text).map(|m| (field, m)))
+                // Prefer the task's own content on ties
+                .max_by_key(|(field, m)| (m.score, *field == MatchField::Content))
+                .map(|(field, matched)| SearchResult {
+                    task_id: task.id.clone(),
+                    field,
+                    matched,
+                })
+        })
+        .collect();
+
+    results.sort_by_key(|result| std::cmp::Reverse(result.matched.score));
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::todoist::test_task;
+
+    fn task(id: &str, content: &str, labels: &[&str], project_id: &str) -> Task {
+        Task {
+            content: content.to_string(),
+            labels: labels.iter().map(|l| l.to_string()).collect(),
+            project_id: project_id.to_string(),
+            ..test_task(id, None, None)
+        }
+    }
+
+    #[test]
+    fn test_fuzzy_match() {
+        let m = fuzzy_match("bm", "Buy milk").unwrap();
+        assert_eq!(m.indices, vec![0, 4]);
+        assert!(fuzzy_match("xyz", "Buy milk").is_none());
+        assert!(fuzzy_match("", "Buy milk").is_none());
+
+        // Consecutive word-start matches beat scattered ones
+        let tight = fuzzy_match("milk", "Buy milk").unwrap();
+        let loose = fuzzy_match("milk", "make it look kind").unwrap();
+        assert!(tight.score > loose.score);
+    }
+
+    #[test]
+    fn test_search_tasks_matches_labels_and_projects() {
+        let tasks = vec![
+            task("1", "Write report", &[], "p1"),
+            task("2", "Call mom", &["family"], "p2"),
+        ];
+        let projects = vec![
+            Project {
+                id: "p1".to_string(),
+                name: "Work".to_string(),
+            },
+            Project {
+                id: "p2".to_string(),
+                name: "Home".to_string(),
+            },
+        ];
+
+        let results = search_tasks("fam", &tasks, &projects);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].task_id, "2");
+        assert_eq!(results[0].field, MatchField::Label("family".to_string()));
+
+        let results = search_tasks("work", &tasks, &projects);
+        assert_eq!(results[0].task_id, "1");
+        assert_eq!(results[0].field, MatchField::Project("Work".to_string()));
+    }
+
+    #[test]
+    fn test_selection_survives_refreshes() {
+        let mut tasks = vec![
+            task("1", "Buy milk", &[], ""),
+            task("2", "Buy bread", &[], ""),
+            task("3", "Buy eggs", &[], ""),
+        ];
+        let mut search = SearchState {
+            query: TextInput::new("buy"),
+            ..SearchState::default()
+        };
+        search.update(&tasks, &[]);
+        search.next();
+        let selected = search.selected().unwrap().task_id.clone();
+
+        // A refresh with a new match keeps the same task selected
+        tasks.insert(0, task("0", "Buy", &[], ""));
+        search.update(&tasks, &[]);
+        assert_eq!(search.selected().unwrap().task_id, selected);
+
+        // Once the task is gone the cursor stays on its row
+        let row = search.list_state.selected();
+        tasks.retain(|task| task.id != selected);
+        search.update(&tasks, &[]);
+        assert_eq!(search.list_state.selected(), row);
+
+        // A new query starts from the best match
+        search.query = TextInput::new("eggs");
+        search.update(&tasks, &[]);
+        assert_eq!(search.list_state.selected(), Some(0));
+    }
+}
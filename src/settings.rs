@@ -0,0 +1,344 @@
+use chrono::format::{Item, StrftimeItems};
+use chrono::Weekday;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::layout::LayoutConfig;
+use crate::theme::UserTheme;
+use crate::toast::Notifications;
+
+/// Name of the settings file in the profile's config directory.
+pub const SETTINGS_FILE: &str = "settings.toml";
+
+/// Fewest seconds between two refreshes, to stay within Todoist's rate
+/// limits.
+pub const MIN_REFRESH_INTERVAL: u64 = 5;
+
+/// Written when there is no settings file yet: every setting with its
+/// default value, commented out.
+pub const TEMPLATE: &str = r##"# todoclist settings
+#
+# Uncomment a line to change a setting; removed or commented lines use the
+# default shown. API keys are never stored here, they stay encrypted in
+# config.json.
+
+# Seconds between two refreshes from Todoist, at least 5.
+# refresh_interval = 30
+
+# View shown on start: "today", "inbox", "calendar" or "agenda".
+# default_view = "today"
+
+# Color theme: "dark", "light", "solarized", "high-contrast" or one of the
+# [themes] below. Switching themes in the app updates this line.
+# theme = "dark"
+
+# Use vim-style bindings, counts and the `:` command line.
+# vim_mode = false
+
+# How due dates are shown, see
+# https://docs.rs/chrono/latest/chrono/format/strftime/
+# date_format = "%Y-%m-%d"
+
+# First day of the week in the calendar and agenda: "monday" or "sunday".
+# week_start = "monday"
+
+# [notifications]
+# Show toasts at all; toasts offering an action, e.g. undo, always show.
+# enabled = true
+# Least severe toasts shown: "info", "success", "warning" or "error".
+# level = "info"
+
+# [layout]
+# Share of the screen for the Today pane, in percent, from 10 to 90.
+# today_ratio = 70
+# Below this many columns only one pane is shown at a time.
+# narrow_width = 60
+# Stack the panes when the terminal is taller than it is wide.
+# stack_when_tall = true
+
+# [keymap]
+# Keys bound to an action, replacing its default keys.
+# toggle_complete = ["x", "space"]
+# quit = ["q", "ctrl-c"]
+
+# [themes.mine]
+# A theme starting from a built-in one, changing some of its colors.
+# base = "light"
+# accent = "#005f87"
+"##;
+
+/// Settings from `settings.toml`, editable by hand and kept apart from the
+/// encrypted keys in `config.json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub refresh_interval: u64,
+    pub default_view: DefaultView,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub theme: Option<String>,
+    pub vim_mode: bool,
+    pub date_format: String,
+    pub week_start: WeekStart,
+    pub notifications: Notifications,
+    pub layout: LayoutConfig,
+    /// Action name -> key bindings
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub keymap: HashMap<String, Vec<String>>,
+    /// User-defined themes by name
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub themes: HashMap<String, UserTheme>,
+}
+
+/// View shown when the app starts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DefaultView {
+    #[default]
+    Today,
+    Inbox,
+    Calendar,
+    Agenda,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WeekStart {
+    #[default]
+    Monday,
+    Sunday,
+}
+
+impl WeekStart {
+    pub fn weekday(&self) -> Weekday {
+        match self {
+            WeekStart::Monday => Weekday::Mon,
+            WeekStart::Sunday => Weekday::Sun,
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            refresh_interval: 30,
+            default_view: DefaultView::default(),
+            theme: None,
+            vim_mode: false,
+            date_format: "%Y-%m-%d".to_string(),
+            week_start: WeekStart::default(),
+            notifications: Notifications::default(),
+            layout: LayoutConfig::default(),
+            keymap: HashMap::new(),
+            themes: HashMap::new(),
+        }
+    }
+}
+
+impl Settings {
+    /// Reads the settings file, writing the commented template if there is
+    /// none yet. Errors name the file and the offending key.
+    pub fn load(path: &Path) -> Result<Settings, String> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                // Best effort: the defaults work without the file
+                let _ = fs::write(path, TEMPLATE);
+                return Ok(Settings::default());
+            }
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+        };
+        Self::parse(&text).map_err(|e| format!("{}: {}", file_name(path), e))
+    }
+
+    /// Parses and validates settings.
+    pub fn parse(text: &str) -> Result<Settings, String> {
+        let settings: Settings = toml::from_str(text).map_err(|e| describe_error(text, &e))?;
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    /// Checks values the types alone do not restrict. Keymaps and themes
+    /// are checked when applied, see
+    /// [`App::apply_settings`](crate::app::App::apply_settings).
+    pub fn validate(&self) -> Result<(), String> {
+        if self.refresh_interval < MIN_REFRESH_INTERVAL {
+            return Err(format!(
+                "`refresh_interval` must be at least {} seconds, got {}",
+                MIN_REFRESH_INTERVAL, self.refresh_interval
+            ));
+        }
+        if self.date_format.is_empty()
+            || StrftimeItems::new(&self.date_format).any(|item| matches!(item, Item::Error))
+        {
+            return Err(format!(
+                "`date_format` is not a valid date format: \"{}\"",
+                self.date_format
+            ));
+        }
+        self.layout.validate()
+    }
+
+    /// Writes a new settings file: the template, followed by `self` unless
+    /// it only holds defaults, e.g. settings moved from `config.json`.
+    pub fn create(&self, path: &Path) -> Result<(), String> {
+        let mut text = TEMPLATE.to_string();
+        if *self != Settings::default() {
+            let values = toml::to_string(self)
+                .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+            text.push_str("\n# Moved from config.json\n");
+            text.push_str(&values);
+        }
+        fs::write(path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+}
+
+/// Sets the top-level `theme` in the settings file, keeping its comments
+/// and everything else as written.
+pub fn save_theme(path: &Path, name: &str) -> Result<(), String> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => TEMPLATE.to_string(),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    let mut document: toml_edit::DocumentMut = text
+        .parse()
+        .map_err(|e| format!("{}: {}", file_name(path), e))?;
+    document["theme"] = toml_edit::value(name);
+    fs::write(path, document.to_string())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// When the settings file was last changed, `None` if it does not exist.
+pub fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// How the settings file is named in messages.
+pub fn file_name(path: &Path) -> String {
+    path.file_name().map_or_else(
+        || path.display().to_string(),
+        |name| name.to_string_lossy().into(),
+    )
+}
+
+/// A parse error as one line: where it is, the key it is about and what is
+/// wrong, e.g. ``line 3: `layout.today_ratio`: invalid type: …``.
+fn describe_error(text: &str, error: &toml::de::Error) -> String {
+    let message = error.message().trim_end();
+    let Some(span) = error.span() else {
+        return message.to_string();
+    };
+    let before = &text[..span.start.min(text.len())];
+    let number = before.matches('\n').count() + 1;
+    let line = text.lines().nth(number - 1).unwrap_or_default();
+
+    // The innermost table header above the error, if any
+    let table = before
+        .lines()
+        .map(str::trim)
+        .rfind(|line| line.starts_with('[') && line.ends_with(']'))
+        .map(|header| header.trim_matches(|c| c == '[' || c == ']').trim());
+    match (line.split_once('='), table) {
+        (Some((key, _)), Some(table)) => {
+            format!("line {}: `{}.{}`: {}", number, table, key.trim(), message)
+        }
+        (Some((key, _)), None) => format!("line {}: `{}`: {}", number, key.trim(), message),
+        (None, _) => format!("line {}: {}", number, message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::toast::Severity;
+
+    #[test]
+    fn test_template_parses_to_defaults() {
+        assert_eq!(Settings::parse(TEMPLATE).unwrap(), Settings::default());
+
+        let settings = Settings::parse(
+            "refresh_interval = 60\nweek_start = \"sunday\"\n\n\
+             [notifications]\nlevel = \"warning\"\n\n[keymap]\nquit = [\"q\"]\n",
+        )
+        .unwrap();
+        assert_eq!(settings.refresh_interval, 60);
+        assert_eq!(settings.week_start.weekday(), Weekday::Sun);
+        assert_eq!(settings.notifications.level, Severity::Warning);
+        assert_eq!(settings.keymap["quit"], vec!["q"]);
+        assert_eq!(settings.layout, LayoutConfig::default());
+    }
+
+    #[test]
+    fn test_template_examples_are_valid() {
+        // Uncomment every example line, keeping the descriptions as comments
+        let uncommented: String = TEMPLATE
+            .lines()
+            .map(|line| match line.strip_prefix("# ") {
+                Some(example)
+                    if (example.starts_with('[') && example.ends_with(']'))
+                        || example.contains(" = ") =>
+                {
+                    example
+                }
+                _ => line,
+            })
+            .map(|line| format!("{}\n", line))
+            .collect();
+        let settings = Settings::parse(&uncommented).unwrap();
+        assert_eq!(settings.keymap["toggle_complete"], vec!["x", "space"]);
+        assert!(settings.themes.contains_key("mine"));
+
+        crate::keymap::Keymap::default()
+            .with_overrides(&settings.keymap)
+            .unwrap();
+        crate::theme::Theme::all(&settings.themes).unwrap();
+    }
+
+    #[test]
+    fn test_errors_name_the_key() {
+        let error = |text: &str| Settings::parse(text).unwrap_err();
+        assert!(error("refresh_interval = 1").starts_with("`refresh_interval` must be"));
+        assert!(error("date_format = \"%Q\"").starts_with("`date_format`"));
+        assert!(error("[layout]\ntoday_ratio = 95").contains("today_ratio"));
+
+        let message = error("theme = \"dark\"\n[layout]\ntoday_ratio = \"wide\"");
+        assert!(
+            message.starts_with("line 3: `layout.today_ratio`:"),
+            "{}",
+            message
+        );
+        let message = error("default_view = \"list\"");
+        assert!(
+            message.starts_with("line 1: `default_view`:"),
+            "{}",
+            message
+        );
+        assert!(error("api_key = \"secret\"").contains("unknown field `api_key`"));
+    }
+
+    #[test]
+    fn test_saving_theme_keeps_comments() {
+        let path = std::env::temp_dir().join(format!("todoclist-{}.toml", uuid::Uuid::new_v4()));
+        Settings {
+            refresh_interval: 60,
+            ..Settings::default()
+        }
+        .create(&path)
+        .unwrap();
+
+        save_theme(&path, "light").unwrap();
+        save_theme(&path, "solarized").unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(text.starts_with(TEMPLATE));
+        assert_eq!(text.matches("\ntheme = ").count(), 1);
+
+        let settings = Settings::load(&path).unwrap();
+        assert_eq!(settings.theme.as_deref(), Some("solarized"));
+        assert_eq!(settings.refresh_interval, 60);
+    }
+}
@@ -0,0 +1,186 @@
+use crate::app::App;
+use crate::todoist::{is_network_error, PendingChange, Project, Task, TodoistClient};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+use tokio::time::Instant;
+
+/// How often queued changes are sent to Todoist.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Results of the background sync, applied to the [`App`] as they arrive.
+#[derive(Debug)]
+pub enum SyncMessage {
+    /// Fresh tasks, and projects unless fetching them failed.
+    Refreshed {
+        tasks: Vec<Task>,
+        projects: Option<Vec<Project>>,
+    },
+    /// Queued changes were sent successfully.
+    Sent { count: usize },
+    /// A task was recreated and Todoist assigned it a new id.
+    Created { old_id: String, task: Box<Task> },
+    /// Changes that could not be sent and go back to the queue.
+    Unsent { changes: Vec<PendingChange> },
+    /// A request failed; `offline` if Todoist could not be reached at all.
+    Failed { error: String, offline: bool },
+}
+
+/// Sends sync results tagged with the [`App::session`] they were produced
+/// for.
+type Sender = mpsc::UnboundedSender<(u64, SyncMessage)>;
+
+/// Spawns the background sync: a loop that flushes queued changes and
+/// refreshes tasks every [`App::refresh_interval`], and a task applying its
+/// results to the app.
+pub fn spawn(app: Arc<Mutex<App>>) {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    let sync_app = Arc::clone(&app);
+    tokio::spawn(async move {
+        let (refresh_requested, flush_requested, mut refresh_interval) = {
+            let app = sync_app.lock().await;
+            (
+                Arc::clone(&app.refresh_requested),
+                Arc::clone(&app.flush_requested),
+                app.refresh_interval,
+            )
+        };
+        let mut flush = tokio::time::interval(FLUSH_INTERVAL);
+        let mut refresh = tokio::time::interval(Duration::from_secs(refresh_interval));
+
+        loop {
+            let refresh_now = tokio::select! {
+                _ = flush.tick() => false,
+                _ = flush_requested.notified() => false,
+                _ = refresh.tick() => true,
+                _ = refresh_requested.notified() => {
+                    refresh.reset();
+                    true
+                }
+            };
+
+            // Reschedule when the interval changed, e.g. in the settings
+            let interval = sync_app.lock().await.refresh_interval;
+            if interval != refresh_interval {
+                refresh_interval = interval;
+                let period = Duration::from_secs(interval);
+                refresh = tokio::time::interval_at(Instant::now() + period, period);
+            }
+
+            // Always flush first so a refresh already reflects our changes
+            if !flush_changes(&sync_app, &tx).await {
+                break;
+            }
+            if refresh_now && !refresh_tasks(&sync_app, &tx).await {
+                break;
+            }
+        }
+    });
+
+    tokio::spawn(async move {
+        while let Some((session, message)) = rx.recv().await {
+            // Drop results for a profile switched away from or logged out of
+            // in the meantime
+            let mut app = app.lock().await;
+            if app.session == session {
+                app.apply_sync_message(message);
+            }
+        }
+    });
+}
+
+/// Sends all queued changes without holding the app lock during requests.
+///
+/// Returns `false` once the receiving side is gone.
+async fn flush_changes(app: &Mutex<App>, tx: &Sender) -> bool {
+    let (client, session, changes) = {
+        let mut app = app.lock().await;
+        let Some(client) = app.todoist_client.clone() else {
+            return true;
+        };
+        let session = app.session;
+        (client, session, std::mem::take(&mut app.pending_changes))
+    };
+    if changes.is_empty() {
+        return true;
+    }
+    send_changes(&client, changes)
+        .await
+        .into_iter()
+        .all(|message| tx.send((session, message)).is_ok())
+}
+
+/// Sends changes in order, stopping at the first failure. The messages
+/// returned report what was sent, the ids Todoist assigned to recreated
+/// tasks and the changes to queue again.
+async fn send_changes(client: &TodoistClient, mut changes: Vec<PendingChange>) -> Vec<SyncMessage> {
+    let mut messages = Vec::new();
+    for i in 0..changes.len() {
+        let result = client.apply_change(&changes[i]).await;
+        if let Ok(Some(task)) = result {
+            // Later changes in this batch may still use the old id
+            let old_id = changes[i].task_id().to_string();
+            for later in &mut changes[i + 1..] {
+                later.remap_task_id(&old_id, &task.id);
+            }
+            messages.push(SyncMessage::Created {
+                old_id,
+                task: Box::new(task),
+            });
+        } else if let Err(e) = result {
+            // Retry everything when offline, but drop a change Todoist
+            // rejected so it does not block the queue forever
+            let offline = is_network_error(e.as_ref());
+            let unsent = if offline { i } else { i + 1 };
+            // Queued again before reporting the rest sent, so the queue is
+            // never seen empty while changes are left
+            messages.push(SyncMessage::Unsent {
+                changes: changes[unsent..].to_vec(),
+            });
+            if i > 0 {
+                messages.push(SyncMessage::Sent { count: i });
+            }
+            messages.push(SyncMessage::Failed {
+                offline,
+                error: e.to_string(),
+            });
+            return messages;
+        }
+    }
+    messages.push(SyncMessage::Sent {
+        count: changes.len(),
+    });
+    messages
+}
+
+/// Fetches tasks and projects, caching them for the next start.
+///
+/// Returns `false` once the receiving side is gone.
+async fn refresh_tasks(app: &Mutex<App>, tx: &Sender) -> bool {
+    let (client, session, profile) = {
+        let app = app.lock().await;
+        let Some(client) = app.todoist_client.clone() else {
+            return true;
+        };
+        (client, app.session, app.profile.clone())
+    };
+
+    let message = match client.get_tasks(None).await {
+        Ok(tasks) => {
+            let projects = client.get_projects().await.ok();
+            // Do not cache the tasks of an account logged out of meanwhile
+            let current = app.lock().await.session == session;
+            if let (true, Some(projects)) = (current, &projects) {
+                // A stale cache only means older tasks until the next refresh
+                let _ = profile.save_cache(&tasks, projects);
+            }
+            SyncMessage::Refreshed { tasks, projects }
+        }
+        Err(e) => SyncMessage::Failed {
+            offline: is_network_error(e.as_ref()),
+            error: format!("Error fetching tasks: {}", e),
+        },
+    };
+    tx.send((session, message)).is_ok()
+}
@@ -0,0 +1,289 @@
+use ratatui::style::{Color, Modifier, Style};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Colors of the interface by what they are used for rather than by hue, so
+/// every part of the UI stays readable under any theme.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    pub text: Color,
+    pub border: Color,
+    /// Background of the selected row.
+    pub selected: Color,
+    /// Text of the selected row.
+    pub selected_text: Color,
+    /// Titles, key names and informational toasts.
+    pub accent: Color,
+    /// Secondary text such as hints, placeholders and due dates.
+    pub muted: Color,
+    pub overdue: Color,
+    /// Tasks marked for a bulk action.
+    pub marked: Color,
+    /// Matched characters in search results.
+    pub highlight: Color,
+    /// Priorities as shown to users; p4 uses `text`.
+    pub priority_1: Color,
+    pub priority_2: Color,
+    pub priority_3: Color,
+    pub success: Color,
+    pub warning: Color,
+    pub error: Color,
+    /// Background of the status bar.
+    pub status_bar: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::dark()
+    }
+}
+
+/// A theme from the settings file: a built-in to start from and the roles it
+/// changes, e.g. `{ base = "light", accent = "#005f87" }`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UserTheme {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+    #[serde(flatten)]
+    pub colors: HashMap<String, String>,
+}
+
+impl Theme {
+    pub const BUILTIN: &'static [&'static str] = &["dark", "light", "solarized", "high-contrast"];
+
+    pub fn builtin(name: &str) -> Option<Theme> {
+        match name {
+            "dark" => Some(Theme::dark()),
+            "light" => Some(Theme::light()),
+            "solarized" => Some(Theme::solarized()),
+            "high-contrast" => Some(Theme::high_contrast()),
+            _ => None,
+        }
+    }
+
+    fn dark() -> Self {
+        Self {
+            name: "dark".to_string(),
+            text: Color::White,
+            border: Color::White,
+            selected: Color::DarkGray,
+            selected_text: Color::White,
+            accent: Color::LightBlue,
+            muted: Color::DarkGray,
+            overdue: Color::LightRed,
+            marked: Color::Yellow,
+            highlight: Color::Yellow,
+            priority_1: Color::LightRed,
+            priority_2: Color::LightYellow,
+            priority_3: Color::LightBlue,
+            success: Color::LightGreen,
+            warning: Color::Yellow,
+            error: Color::LightRed,
+            status_bar: Color::Black,
+        }
+    }
+
+    fn light() -> Self {
+        Self {
+            name: "light".to_string(),
+            text: Color::Black,
+            border: Color::DarkGray,
+            selected: Color::Gray,
+            selected_text: Color::Black,
+            accent: Color::Blue,
+            muted: Color::DarkGray,
+            overdue: Color::Red,
+            marked: Color::Magenta,
+            highlight: Color::Magenta,
+            priority_1: Color::Red,
+            priority_2: Color::Rgb(175, 95, 0),
+            priority_3: Color::Blue,
+            success: Color::Green,
+            warning: Color::Rgb(175, 95, 0),
+            error: Color::Red,
+            status_bar: Color::Gray,
+        }
+    }
+
+    fn solarized() -> Self {
+        // https://ethanschoonover.com/solarized/
+        let base02 = Color::Rgb(0x07, 0x36, 0x42);
+        let base01 = Color::Rgb(0x58, 0x6e, 0x75);
+        let base0 = Color::Rgb(0x83, 0x94, 0x96);
+        let base1 = Color::Rgb(0x93, 0xa1, 0xa1);
+        let yellow = Color::Rgb(0xb5, 0x89, 0x00);
+        let orange = Color::Rgb(0xcb, 0x4b, 0x16);
+        let red = Color::Rgb(0xdc, 0x32, 0x2f);
+        let magenta = Color::Rgb(0xd3, 0x36, 0x82);
+        let blue = Color::Rgb(0x26, 0x8b, 0xd2);
+        let green = Color::Rgb(0x85, 0x99, 0x00);
+        Self {
+            name: "solarized".to_string(),
+            text: base0,
+            border: base01,
+            selected: base02,
+            selected_text: base1,
+            accent: blue,
+            muted: base01,
+            overdue: red,
+            marked: magenta,
+            highlight: yellow,
+            priority_1: red,
+            priority_2: orange,
+            priority_3: blue,
+            success: green,
+            warning: yellow,
+            error: red,
+            status_bar: base02,
+        }
+    }
+
+    fn high_contrast() -> Self {
+        Self {
+            name: "high-contrast".to_string(),
+            text: Color::White,
+            border: Color::White,
+            selected: Color::White,
+            selected_text: Color::Black,
+            accent: Color::LightCyan,
+            muted: Color::Gray,
+            overdue: Color::LightRed,
+            marked: Color::LightYellow,
+            highlight: Color::LightYellow,
+            priority_1: Color::LightRed,
+            priority_2: Color::LightYellow,
+            priority_3: Color::LightCyan,
+            success: Color::LightGreen,
+            warning: Color::LightYellow,
+            error: Color::LightRed,
+            status_bar: Color::Black,
+        }
+    }
+
+    /// Builds a user theme on top of its base, the dark theme by default.
+    pub fn from_user(name: &str, user: &UserTheme) -> Result<Theme, String> {
+        let base = user.base.as_deref().unwrap_or("dark");
+        let mut theme = Theme::builtin(base)
+            .ok_or_else(|| format!("theme `{}`: unknown base theme `{}`", name, base))?;
+        theme.name = name.to_string();
+
+        let mut roles: Vec<&String> = user.colors.keys().collect();
+        roles.sort();
+        for role in roles {
+            let value = &user.colors[role];
+            let color = Color::from_str(value).map_err(|_| {
+                format!("theme `{}`: invalid color `{}` for `{}`", name, value, role)
+            })?;
+            *theme
+                .role_mut(role)
+                .ok_or_else(|| format!("theme `{}`: unknown color role `{}`", name, role))? = color;
+        }
+        Ok(theme)
+    }
+
+    fn role_mut(&mut self, role: &str) -> Option<&mut Color> {
+        Some(match role {
+            "text" => &mut self.text,
+            "border" => &mut self.border,
+            "selected" => &mut self.selected,
+            "selected_text" => &mut self.selected_text,
+            "accent" => &mut self.accent,
+            "muted" => &mut self.muted,
+            "overdue" => &mut self.overdue,
+            "marked" => &mut self.marked,
+            "highlight" => &mut self.highlight,
+            "priority_1" => &mut self.priority_1,
+            "priority_2" => &mut self.priority_2,
+            "priority_3" => &mut self.priority_3,
+            "success" => &mut self.success,
+            "warning" => &mut self.warning,
+            "error" => &mut self.error,
+            "status_bar" => &mut self.status_bar,
+            _ => return None,
+        })
+    }
+
+    /// Built-in themes followed by the user's, sorted by name.
+    pub fn all(user_themes: &HashMap<String, UserTheme>) -> Result<Vec<Theme>, String> {
+        let mut themes: Vec<Theme> = Theme::BUILTIN
+            .iter()
+            .filter_map(|name| Theme::builtin(name))
+            .collect();
+        let mut names: Vec<&String> = user_themes.keys().collect();
+        names.sort();
+        for name in names {
+            let theme = Theme::from_user(name, &user_themes[name])?;
+            // A user theme may redefine a built-in one
+            themes.retain(|existing| existing.name != theme.name);
+            themes.push(theme);
+        }
+        Ok(themes)
+    }
+
+    pub fn text_style(&self) -> Style {
+        Style::default().fg(self.text)
+    }
+
+    pub fn border_style(&self) -> Style {
+        Style::default().fg(self.border)
+    }
+
+    pub fn selected_style(&self) -> Style {
+        Style::default().bg(self.selected).fg(self.selected_text)
+    }
+
+    pub fn muted_style(&self) -> Style {
+        Style::default().fg(self.muted)
+    }
+
+    pub fn accent_style(&self) -> Style {
+        Style::default().fg(self.accent)
+    }
+
+    pub fn heading_style(&self) -> Style {
+        Style::default().add_modifier(Modifier::BOLD)
+    }
+
+    /// Color of a Todoist priority, where 4 is the most urgent.
+    pub fn priority(&self, priority: u8) -> Color {
+        match priority {
+            4 => self.priority_1,
+            3 => self.priority_2,
+            2 => self.priority_3,
+            _ => self.text,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_themes() {
+        let user = UserTheme {
+            base: Some("light".to_string()),
+            colors: HashMap::from([
+                ("accent".to_string(), "#005f87".to_string()),
+                ("border".to_string(), "magenta".to_string()),
+            ]),
+        };
+        let theme = Theme::from_user("mine", &user).unwrap();
+        assert_eq!(theme.accent, Color::Rgb(0x00, 0x5f, 0x87));
+        assert_eq!(theme.border, Color::Magenta);
+        assert_eq!(theme.text, Theme::light().text);
+
+        let themes = Theme::all(&HashMap::from([("mine".to_string(), user)])).unwrap();
+        assert_eq!(themes.len(), Theme::BUILTIN.len() + 1);
+
+        let invalid = UserTheme {
+            base: None,
+            colors: HashMap::from([("boder".to_string(), "red".to_string())]),
+        };
+        assert!(Theme::from_user("typo", &invalid)
+            .unwrap_err()
+            .contains("`boder`"));
+    }
+}
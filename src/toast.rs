@@ -0,0 +1,167 @@
+use crate::keymap::Action;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Most toasts kept at once; older ones are dropped first.
+const MAX_TOASTS: usize = 5;
+
+/// How serious a toast is, from least to most.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    /// How long a toast of this severity stays on screen.
+    fn duration(&self) -> Duration {
+        match self {
+            Severity::Info | Severity::Success => Duration::from_secs(4),
+            Severity::Warning => Duration::from_secs(6),
+            Severity::Error => Duration::from_secs(8),
+        }
+    }
+}
+
+/// Which toasts are shown, from the settings file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Notifications {
+    pub enabled: bool,
+    /// Least severe toasts shown.
+    pub level: Severity,
+}
+
+impl Default for Notifications {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            level: Severity::Info,
+        }
+    }
+}
+
+impl Notifications {
+    /// Toasts offering an action are always shown, the action would be
+    /// out of reach otherwise.
+    fn shows(&self, severity: Severity, action: Option<Action>) -> bool {
+        action.is_some() || (self.enabled && severity >= self.level)
+    }
+}
+
+/// A short-lived notification shown in the corner of the screen.
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub message: String,
+    pub severity: Severity,
+    /// Action offered alongside the message, e.g. undo after completing a task.
+    pub action: Option<Action>,
+    pub expires_at: Instant,
+}
+
+/// Toasts in the order they were raised.
+#[derive(Debug, Default)]
+pub struct Toasts {
+    queue: VecDeque<Toast>,
+    pub notifications: Notifications,
+}
+
+impl Toasts {
+    pub fn push(&mut self, severity: Severity, message: impl Into<String>) {
+        self.push_with_action(severity, message, None);
+    }
+
+    pub fn push_with_action(
+        &mut self,
+        severity: Severity,
+        message: impl Into<String>,
+        action: Option<Action>,
+    ) {
+        if !self.notifications.shows(severity, action) {
+            return;
+        }
+        // Toasts with an action stay a little longer to leave time to use it
+        let mut duration = severity.duration();
+        if action.is_some() {
+            duration += Duration::from_secs(2);
+        }
+        self.queue.push_back(Toast {
+            message: message.into(),
+            severity,
+            action,
+            expires_at: Instant::now() + duration,
+        });
+        while self.queue.len() > MAX_TOASTS {
+            self.queue.pop_front();
+        }
+    }
+
+    /// Drops toasts whose time is up, called on every tick.
+    pub fn expire(&mut self, now: Instant) {
+        self.queue.retain(|toast| toast.expires_at > now);
+    }
+
+    /// Dismisses visible toasts offering `action` once it was used.
+    pub fn dismiss_action(&mut self, action: Action) {
+        self.queue.retain(|toast| toast.action != Some(action));
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Toast> {
+        self.queue.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toasts_expire_and_dismiss() {
+        let mut toasts = Toasts::default();
+        toasts.push(Severity::Info, "Synced");
+        toasts.push_with_action(Severity::Success, "Task completed", Some(Action::Undo));
+        toasts.push(Severity::Error, "Sync failed");
+
+        toasts.expire(Instant::now() + Duration::from_secs(5));
+        assert_eq!(toasts.iter().count(), 2);
+
+        toasts.dismiss_action(Action::Undo);
+        assert_eq!(toasts.iter().next().unwrap().message, "Sync failed");
+
+        toasts.expire(Instant::now() + Duration::from_secs(60));
+        assert!(toasts.is_empty());
+    }
+
+    #[test]
+    fn test_toasts_are_capped() {
+        let mut toasts = Toasts::default();
+        for i in 0..MAX_TOASTS + 2 {
+            toasts.push(Severity::Info, format!("toast {}", i));
+        }
+        assert_eq!(toasts.iter().count(), MAX_TOASTS);
+        assert_eq!(toasts.iter().next().unwrap().message, "toast 2");
+    }
+
+    #[test]
+    fn test_notification_settings_filter_toasts() {
+        let mut toasts = Toasts::default();
+        toasts.notifications.level = Severity::Warning;
+        toasts.push(Severity::Success, "Synced");
+        toasts.push(Severity::Error, "Sync failed");
+        assert_eq!(toasts.iter().count(), 1);
+
+        toasts.notifications.enabled = false;
+        toasts.push(Severity::Error, "Sync failed");
+        toasts.push_with_action(Severity::Success, "Task completed", Some(Action::Undo));
+        let messages: Vec<&str> = toasts.iter().map(|toast| toast.message.as_str()).collect();
+        assert_eq!(messages, vec!["Sync failed", "Task completed"]);
+    }
+}
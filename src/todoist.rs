@@ -1,8 +1,9 @@
-use serde::Deserialize;
+use chrono::{Datelike, Duration as Days, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
 use std::error::Error;
 use std::time::Duration;
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Task {
     pub id: String,
     pub content: String,
@@ -10,28 +11,334 @@ pub struct Task {
     pub is_completed: bool,
     pub labels: Vec<String>,
     pub due: Option<DueDate>,
+    /// Todoist priority from 1 (normal) to 4 (urgent), shown as p4 to p1.
+    #[serde(default = "default_priority")]
+    pub priority: u8,
+    #[serde(default)]
+    pub project_id: String,
+    /// How long the task takes, for tasks blocked out in the calendar.
+    #[serde(default)]
+    pub duration: Option<TaskDuration>,
 }
 
-#[derive(Debug)]
+fn default_priority() -> u8 {
+    1
+}
+
+/// Todoist task duration, e.g. `{"amount": 45, "unit": "minute"}`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TaskDuration {
+    pub amount: u32,
+    /// `minute` or `day`.
+    pub unit: String,
+}
+
+impl TaskDuration {
+    pub fn minutes(&self) -> i64 {
+        match self.unit.as_str() {
+            "day" => self.amount as i64 * 24 * 60,
+            _ => self.amount as i64,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Project {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum PendingChange {
-    TaskCompletion { task_id: String, completed: bool },
+    TaskCompletion {
+        task_id: String,
+        completed: bool,
+    },
+    TaskDeletion {
+        task_id: String,
+    },
+    TaskUpdate {
+        task_id: String,
+        update: TaskUpdate,
+    },
+    TaskMove {
+        task_id: String,
+        project_id: String,
+    },
+    /// Recreates a task, used to undo a deletion that was already sent.
+    /// Todoist assigns a new id, which replaces `task.id` everywhere once
+    /// the task was created.
+    TaskCreation {
+        task: Task,
+    },
 }
 
-impl Clone for PendingChange {
-    fn clone(&self) -> Self {
-        match &self {
+impl PendingChange {
+    pub fn task_id(&self) -> &str {
+        match self {
+            PendingChange::TaskCompletion { task_id, .. }
+            | PendingChange::TaskDeletion { task_id }
+            | PendingChange::TaskUpdate { task_id, .. }
+            | PendingChange::TaskMove { task_id, .. } => task_id,
+            PendingChange::TaskCreation { task } => &task.id,
+        }
+    }
+
+    /// Points the change at the id Todoist gave a recreated task.
+    pub fn remap_task_id(&mut self, old_id: &str, new_id: &str) {
+        match self {
+            PendingChange::TaskCompletion { task_id, .. }
+            | PendingChange::TaskDeletion { task_id }
+            | PendingChange::TaskUpdate { task_id, .. }
+            | PendingChange::TaskMove { task_id, .. } => {
+                if task_id == old_id {
+                    *task_id = new_id.to_string();
+                }
+            }
+            PendingChange::TaskCreation { task } => {
+                if task.id == old_id {
+                    task.id = new_id.to_string();
+                }
+            }
+        }
+    }
+
+    /// Applies the change to the local task list, ahead of Todoist.
+    pub fn apply_locally(&self, tasks: &mut Vec<Task>) {
+        let Some(index) = tasks.iter().position(|task| task.id == self.task_id()) else {
+            if let PendingChange::TaskCreation { task } = self {
+                tasks.push(task.clone());
+            }
+            return;
+        };
+        let task = &mut tasks[index];
+        match self {
+            PendingChange::TaskCompletion { completed, .. } => task.is_completed = *completed,
+            PendingChange::TaskUpdate { update, .. } => update.apply_to(task),
+            PendingChange::TaskMove { project_id, .. } => task.project_id = project_id.clone(),
+            PendingChange::TaskDeletion { .. } => {
+                tasks.remove(index);
+            }
+            PendingChange::TaskCreation { .. } => {}
+        }
+    }
+
+    /// The change reverting this one, given the task as it was before.
+    pub fn inverse(&self, before: &Task) -> PendingChange {
+        match self {
             PendingChange::TaskCompletion { task_id, completed } => PendingChange::TaskCompletion {
                 task_id: task_id.clone(),
-                completed: completed.clone(),
+                completed: !completed,
+            },
+            PendingChange::TaskDeletion { .. } => PendingChange::TaskCreation {
+                task: before.clone(),
+            },
+            PendingChange::TaskUpdate { task_id, update } => PendingChange::TaskUpdate {
+                task_id: task_id.clone(),
+                update: update.revert(before),
+            },
+            PendingChange::TaskMove { task_id, .. } => PendingChange::TaskMove {
+                task_id: task_id.clone(),
+                project_id: before.project_id.clone(),
+            },
+            PendingChange::TaskCreation { task } => PendingChange::TaskDeletion {
+                task_id: task.id.clone(),
             },
         }
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+/// Fields to change on a task; `None` leaves a field as it is.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TaskUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    /// Natural language due date such as `tomorrow`, `no date` clears it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due_string: Option<String>,
+    /// Due day such as `2024-03-04`, without a time.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due_date: Option<String>,
+    /// Due time in RFC 3339 and UTC, see [`utc_datetime`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due_datetime: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<u8>,
+    /// The complete new list of labels.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub labels: Option<Vec<String>>,
+}
+
+impl TaskUpdate {
+    pub fn apply_to(&self, task: &mut Task) {
+        if let Some(content) = &self.content {
+            task.content = content.clone();
+        }
+        if let Some(due_string) = &self.due_string {
+            let today = chrono::Local::now().date_naive();
+            if is_no_date(due_string) {
+                task.due = None;
+            } else if let Some(date) = parse_due_date(due_string, today) {
+                task.due = Some(DueDate {
+                    string: due_string.clone(),
+                    date: date.to_string(),
+                    datetime: None,
+                    is_recurring: false,
+                });
+            }
+            // Anything else is left to Todoist and shows up on the next refresh
+        }
+        if let Some(date) = &self.due_date {
+            task.due = Some(DueDate {
+                string: date.clone(),
+                date: date.clone(),
+                datetime: None,
+                is_recurring: false,
+            });
+        }
+        if let Some(local) = self.due_datetime.as_deref().and_then(local_datetime) {
+            task.due = Some(DueDate {
+                string: local.format("%Y-%m-%d %H:%M").to_string(),
+                date: local.date().to_string(),
+                datetime: self.due_datetime.clone(),
+                is_recurring: false,
+            });
+        }
+        if let Some(priority) = self.priority {
+            task.priority = priority;
+        }
+        if let Some(labels) = &self.labels {
+            task.labels = labels.clone();
+        }
+    }
+
+    /// The update restoring the fields this one touches to their values on `before`.
+    pub fn revert(&self, before: &Task) -> TaskUpdate {
+        let mut revert = TaskUpdate {
+            content: self.content.as_ref().map(|_| before.content.clone()),
+            priority: self.priority.map(|_| before.priority),
+            labels: self.labels.as_ref().map(|_| before.labels.clone()),
+            ..Default::default()
+        };
+        if self.due_string.is_some() || self.due_date.is_some() || self.due_datetime.is_some() {
+            // Todoist would read a date like `tomorrow` relative to the time
+            // of the undo, so only recurring dates are sent as text
+            match &before.due {
+                None => revert.due_string = Some("no date".to_string()),
+                Some(due) if due.is_recurring => revert.due_string = Some(due.string.clone()),
+                Some(due) => match due.datetime.as_deref().and_then(utc_datetime) {
+                    Some(datetime) => revert.due_datetime = Some(datetime),
+                    None => revert.due_date = Some(due.date.clone()),
+                },
+            }
+        }
+        revert
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct DueDate {
     pub string: String,
     pub date: String,
+    /// Due time for tasks with one, in UTC when it ends with `Z` and local
+    /// time otherwise.
+    #[serde(default)]
+    pub datetime: Option<String>,
+    #[serde(default)]
+    pub is_recurring: bool,
+}
+
+/// A due time in local time, converting it from UTC when it ends with `Z`.
+pub fn local_datetime(datetime: &str) -> Option<NaiveDateTime> {
+    if datetime.ends_with('Z') {
+        let utc = chrono::DateTime::parse_from_rfc3339(datetime).ok()?;
+        Some(utc.with_timezone(&Local).naive_local())
+    } else {
+        NaiveDateTime::parse_from_str(datetime, "%Y-%m-%dT%H:%M:%S").ok()
+    }
+}
+
+/// A due time as Todoist's `due_datetime` takes it, in UTC. Times without
+/// `Z` are local time.
+pub fn utc_datetime(datetime: &str) -> Option<String> {
+    if datetime.ends_with('Z') {
+        return Some(datetime.to_string());
+    }
+    let local = NaiveDateTime::parse_from_str(datetime, "%Y-%m-%dT%H:%M:%S").ok()?;
+    let utc = Local
+        .from_local_datetime(&local)
+        .single()?
+        .with_timezone(&Utc);
+    Some(utc.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+}
+
+fn is_no_date(input: &str) -> bool {
+    matches!(
+        input.trim().to_lowercase().as_str(),
+        "" | "no date" | "no due date" | "none"
+    )
+}
+
+/// Resolves the due dates we can show right away: `today`, `tomorrow`,
+/// weekday names, `in 3 days` and ISO dates. Todoist understands far more,
+/// everything else is resolved on the next refresh.
+pub fn parse_due_date(input: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let input = input.trim().to_lowercase();
+    match input.as_str() {
+        "today" | "tod" => return Some(today),
+        "tomorrow" | "tom" => return Some(today + Days::days(1)),
+        _ => {}
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(&input, "%Y-%m-%d") {
+        return Some(date);
+    }
+    if let Some(days) = input
+        .strip_prefix("in ")
+        .and_then(|rest| rest.strip_suffix(" days").or(rest.strip_suffix(" day")))
+        .and_then(|n| n.trim().parse::<i64>().ok())
+    {
+        // Counts too large for a date are left to Todoist
+        return Days::try_days(days).and_then(|days| today.checked_add_signed(days));
+    }
+
+    // The next such weekday, a week ahead when it is today
+    let weekday = input.strip_prefix("next ").unwrap_or(&input);
+    let weekday: chrono::Weekday = weekday.parse().ok()?;
+    let ahead = (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+    Some(today + Days::days(if ahead == 0 { 7 } else { ahead as i64 }))
+}
+
+/// Whether a request failed because Todoist could not be reached, as opposed
+/// to Todoist rejecting it.
+pub fn is_network_error(error: &(dyn Error + Send + Sync + 'static)) -> bool {
+    error
+        .downcast_ref::<reqwest::Error>()
+        .is_some_and(|e| e.is_connect() || e.is_timeout() || e.is_request())
+}
+
+/// Why an API key could not be confirmed during onboarding.
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyError {
+    /// Todoist answered 401 or 403: the key is wrong or revoked.
+    Rejected,
+    /// Todoist could not be reached at all.
+    Network(String),
+    /// Anything else, e.g. a server error.
+    Other(String),
+}
+
+impl std::fmt::Display for KeyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeyError::Rejected => {
+                write!(f, "Todoist rejected this API key, check it and try again")
+            }
+            KeyError::Network(e) => {
+                write!(f, "Could not reach Todoist, press Enter to retry: {}", e)
+            }
+            KeyError::Other(e) => write!(f, "Could not verify the API key: {}", e),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -63,8 +370,7 @@ impl TodoistClient {
             if !response.status().is_success() {
                 let status = response.status();
                 let error_body = response.text().await?;
-                eprintln!("Failed to close task: {} - {}", status, error_body);
-                return Err(format!("API request failed: {}", status).into());
+                return Err(format!("Failed to close task: {} - {}", status, error_body).into());
             }
         } else {
             // Reopen the task
@@ -79,13 +385,161 @@ impl TodoistClient {
             if !response.status().is_success() {
                 let status = response.status();
                 let error_body = response.text().await?;
-                eprintln!("Failed to reopen task: {} - {}", status, error_body);
-                return Err(format!("API request failed: {}", status).into());
+                return Err(format!("Failed to reopen task: {} - {}", status, error_body).into());
             }
         }
 
         Ok(())
     }
+
+    pub(crate) async fn delete_task(
+        &self,
+        task_id: &String,
+    ) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+        let endpoint = format!("https://api.todoist.com/rest/v2/tasks/{}", task_id);
+        let response = self
+            .client
+            .delete(&endpoint)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_body = response.text().await?;
+            return Err(format!("Failed to delete task: {} - {}", status, error_body).into());
+        }
+
+        Ok(())
+    }
+
+    pub(crate) async fn update_task(
+        &self,
+        task_id: &String,
+        update: &TaskUpdate,
+    ) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+        let endpoint = format!("https://api.todoist.com/rest/v2/tasks/{}", task_id);
+        let response = self
+            .client
+            .post(&endpoint)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .json(update)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_body = response.text().await?;
+            return Err(format!("Failed to update task: {} - {}", status, error_body).into());
+        }
+
+        Ok(())
+    }
+
+    /// Moves a task to another project. The REST API cannot change a task's
+    /// project, so this goes through the Sync API's `item_move` command.
+    pub(crate) async fn move_task(
+        &self,
+        task_id: &String,
+        project_id: &String,
+    ) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+        let commands = serde_json::json!([{
+            "type": "item_move",
+            "uuid": uuid::Uuid::new_v4().to_string(),
+            "args": { "id": task_id, "project_id": project_id },
+        }]);
+        let response = self
+            .client
+            .post("https://api.todoist.com/sync/v9/sync")
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .form(&[("commands", commands.to_string())])
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_body = response.text().await?;
+            return Err(format!("Failed to move task: {} - {}", status, error_body).into());
+        }
+
+        // Commands fail individually while the request itself succeeds
+        let body: serde_json::Value = response.json().await?;
+        let command_status = body["sync_status"]
+            .as_object()
+            .and_then(|statuses| statuses.values().next());
+        match command_status {
+            Some(status) if status == "ok" => Ok(()),
+            Some(status) => Err(format!("Failed to move task: {}", status).into()),
+            None => Err("Failed to move task: no command status in response".into()),
+        }
+    }
+
+    /// Creates a task with the content, labels, project and due date of
+    /// `task`, returning it with the id Todoist assigned.
+    pub(crate) async fn create_task(
+        &self,
+        task: &Task,
+    ) -> Result<Task, Box<dyn Error + Send + Sync + 'static>> {
+        let mut body = serde_json::json!({
+            "content": task.content,
+            "description": task.description,
+            "labels": task.labels,
+            "priority": task.priority,
+        });
+        if !task.project_id.is_empty() {
+            body["project_id"] = task.project_id.clone().into();
+        }
+        // Todoist reads `due_datetime` as UTC, local times are converted
+        if let Some(due) = &task.due {
+            match due.datetime.as_deref().and_then(utc_datetime) {
+                Some(datetime) => body["due_datetime"] = datetime.into(),
+                None => body["due_date"] = due.date.clone().into(),
+            }
+        }
+        if let Some(duration) = &task.duration {
+            body["duration"] = duration.amount.into();
+            body["duration_unit"] = duration.unit.clone().into();
+        }
+
+        let response = self
+            .client
+            .post("https://api.todoist.com/rest/v2/tasks")
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .json(&body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_body = response.text().await?;
+            return Err(format!("Failed to create task: {} - {}", status, error_body).into());
+        }
+
+        Ok(response.json().await?)
+    }
+
+    /// Sends a single queued change to Todoist, returning the created task
+    /// for a [`PendingChange::TaskCreation`].
+    pub(crate) async fn apply_change(
+        &self,
+        change: &PendingChange,
+    ) -> Result<Option<Task>, Box<dyn Error + Send + Sync + 'static>> {
+        match change {
+            PendingChange::TaskCompletion { task_id, completed } => {
+                self.update_task_completion(task_id, *completed).await?
+            }
+            PendingChange::TaskDeletion { task_id } => self.delete_task(task_id).await?,
+            PendingChange::TaskUpdate { task_id, update } => {
+                self.update_task(task_id, update).await?
+            }
+            PendingChange::TaskMove {
+                task_id,
+                project_id,
+            } => self.move_task(task_id, project_id).await?,
+            PendingChange::TaskCreation { task } => return Ok(Some(self.create_task(task).await?)),
+        }
+        Ok(None)
+    }
 }
 
 impl TodoistClient {
@@ -118,8 +572,7 @@ impl TodoistClient {
         if !response.status().is_success() {
             let status = response.status();
             let error_body = response.text().await?;
-            eprintln!("API request failed with status {}: {}", status, error_body);
-            return Err(format!("API request failed: {}", status).into());
+            return Err(format!("API request failed: {} - {}", status, error_body).into());
         }
 
         // Print raw response for debugging
@@ -129,4 +582,161 @@ impl TodoistClient {
         let tasks: Vec<Task> = serde_json::from_str(&raw_json)?;
         Ok(tasks)
     }
+
+    /// Checks the key with the cheapest authenticated request there is.
+    pub async fn verify_key(&self) -> Result<(), KeyError> {
+        let response = self
+            .client
+            .get("https://api.todoist.com/rest/v2/projects")
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .send()
+            .await
+            .map_err(|e| {
+                if is_network_error(&e) {
+                    KeyError::Network(e.to_string())
+                } else {
+                    KeyError::Other(e.to_string())
+                }
+            })?;
+
+        match response.status() {
+            status if status.is_success() => Ok(()),
+            reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN => {
+                Err(KeyError::Rejected)
+            }
+            status => Err(KeyError::Other(format!("API request failed: {}", status))),
+        }
+    }
+
+    pub async fn get_projects(
+        &self,
+    ) -> Result<Vec<Project>, Box<dyn Error + Send + Sync + 'static>> {
+        let response = self
+            .client
+            .get("https://api.todoist.com/rest/v2/projects")
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_body = response.text().await?;
+            return Err(format!("API request failed: {} - {}", status, error_body).into());
+        }
+
+        let projects: Vec<Project> = response.json().await?;
+        Ok(projects)
+    }
+}
+
+/// An open task for tests, due on a day (`2024-03-04`) or at a time
+/// (`2024-03-04T09:00:00`) and taking `minutes`, if given.
+#[cfg(test)]
+pub fn test_task(id: &str, due: Option<&str>, minutes: Option<u32>) -> Task {
+    Task {
+        id: id.to_string(),
+        content: format!("Task {}", id),
+        description: String::new(),
+        is_completed: false,
+        labels: Vec::new(),
+        due: due.map(|due| DueDate {
+            string: due.to_string(),
+            date: due.get(..10).unwrap_or(due).to_string(),
+            datetime: (due.len() > 10).then(|| due.to_string()),
+            is_recurring: false,
+        }),
+        priority: 1,
+        project_id: String::new(),
+        duration: minutes.map(|amount| TaskDuration {
+            amount,
+            unit: "minute".to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_due_date() {
+        // A Monday
+        let today = NaiveDate::from_ymd_opt(2024, 5, 6).unwrap();
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d);
+
+        assert_eq!(parse_due_date("Today", today), Some(today));
+        assert_eq!(parse_due_date("tomorrow", today), date(2024, 5, 7));
+        assert_eq!(parse_due_date("in 3 days", today), date(2024, 5, 9));
+        assert_eq!(parse_due_date("2024-12-24", today), date(2024, 12, 24));
+        assert_eq!(parse_due_date("friday", today), date(2024, 5, 10));
+        assert_eq!(parse_due_date("next monday", today), date(2024, 5, 13));
+        assert_eq!(parse_due_date("every other week", today), None);
+        assert_eq!(parse_due_date("in -2 days", today), date(2024, 5, 4));
+        assert_eq!(parse_due_date("in 99999999999 days", today), None);
+        assert_eq!(
+            parse_due_date(&format!("in {} days", i64::MAX), today),
+            None
+        );
+    }
+
+    #[test]
+    fn test_utc_datetime() {
+        let utc = "2024-03-04T08:30:00Z";
+        assert_eq!(utc_datetime(utc).as_deref(), Some(utc));
+
+        // A local time names the same moment once converted
+        let local = "2024-03-04T09:30:00";
+        let converted = utc_datetime(local).unwrap();
+        assert!(converted.ends_with('Z'));
+        assert_eq!(local_datetime(&converted), local_datetime(local));
+        assert_eq!(utc_datetime("tomorrow at 9"), None);
+    }
+
+    #[test]
+    fn test_revert_restores_the_exact_due_date() {
+        let reschedule = TaskUpdate {
+            due_string: Some("friday".to_string()),
+            ..Default::default()
+        };
+        let undo = |before: &Task| {
+            let mut task = before.clone();
+            reschedule.apply_to(&mut task);
+            let revert = reschedule.revert(before);
+            revert.apply_to(&mut task);
+            (revert, task)
+        };
+
+        let mut before = test_task("1", Some("2024-03-04"), None);
+        before.due.as_mut().unwrap().string = "tomorrow".to_string();
+        let (revert, task) = undo(&before);
+        assert_eq!(revert.due_string, None);
+        assert_eq!(revert.due_date.as_deref(), Some("2024-03-04"));
+        assert_eq!(task.due.unwrap().date, "2024-03-04");
+
+        let before = test_task("2", Some("2024-03-04T09:30:00"), None);
+        let (revert, task) = undo(&before);
+        assert_eq!(revert.due_date, None);
+        assert!(revert.due_datetime.as_deref().unwrap().ends_with('Z'));
+        let due = task.due.unwrap();
+        assert_eq!(due.date, "2024-03-04");
+        assert_eq!(
+            local_datetime(due.datetime.as_deref().unwrap()),
+            local_datetime("2024-03-04T09:30:00")
+        );
+
+        let mut before = test_task("3", Some("2024-03-04"), None);
+        let due = before.due.as_mut().unwrap();
+        due.string = "every mon".to_string();
+        due.is_recurring = true;
+        assert_eq!(
+            reschedule.revert(&before).due_string.as_deref(),
+            Some("every mon")
+        );
+
+        let before = test_task("4", None, None);
+        assert_eq!(
+            reschedule.revert(&before).due_string.as_deref(),
+            Some("no date")
+        );
+    }
 }
@@ -1,14 +1,38 @@
 use ratatui::{
-    layout::{Alignment, Constraint, Direction, Layout},
-    style::{Color, Style},
-    widgets::{Block, List, ListItem, Paragraph},
+    layout::{Alignment, Constraint, Direction, Flex, Layout, Margin, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{
+        Block, Cell, Clear, HighlightSpacing, List, ListItem, Paragraph, Row, Scrollbar,
+        ScrollbarOrientation, ScrollbarState, Table, Tabs,
+    },
     Frame,
 };
+use chrono::{Datelike, Timelike};
+use std::time::Duration;
 
+use crate::agenda::{self, TimeBlock};
 use crate::app::App;
+use crate::calendar;
+use crate::input::{self, TextInput};
+use crate::keymap::{format_sequence, Action, Mode};
+use crate::layout::{pane_areas, ListArea, Pane, RenderedAreas};
+use crate::prompt::{match_project, parse_priority, PromptKind};
+use crate::search::{MatchField, SearchResult};
+use crate::theme::Theme;
+use crate::toast::Severity;
+use crate::todoist::{parse_due_date, Project, Task};
+
+/// Frames of the spinner shown while the API key is checked.
+const SPINNER: &[&str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
 
 pub fn render(app: &mut App, frame: &mut Frame) {
-    if !app.onboarding_complete && app.api_key.is_none() {
+    let theme = &app.theme;
+    if !app.onboarding_complete {
+        let [main_area, footer_area] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        render_footer(app, frame, footer_area);
+
         let layout = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
@@ -16,61 +40,81 @@ pub fn render(app: &mut App, frame: &mut Frame) {
                 Constraint::Length(3),
                 Constraint::Length(3),
             ])
-            .split(frame.area());
+            .split(main_area);
+
+        // The same screen asks for the vault passphrase when keys are locked
+        let (welcome, title, field) = if app.vault_locked {
+            ("Your API key is locked. Please enter your passphrase:", " Unlock ", " Passphrase ")
+        } else if app.api_key.is_some() {
+            ("Enter the new API key, or press Esc to keep yours:", " Change API Key ", " API Key ")
+        } else {
+            ("Welcome! Please enter your API key:", " API Key Setup ", " API Key ")
+        };
 
         // Title
         frame.render_widget(
-            Paragraph::new("Welcome! Please enter your API key:")
+            Paragraph::new(welcome)
                 .block(
                     Block::bordered()
-                        .title(" API Key Setup ")
+                        .title(title)
                         .title_alignment(Alignment::Center)
-                        .style(Style::default().fg(Color::LightBlue))
+                        .style(theme.accent_style())
                 )
                 .alignment(Alignment::Center)
-                .style(Style::default().fg(Color::White)),
+                .style(theme.text_style()),
             layout[0],
         );
 
-        // Input field with placeholder
-        let display_text = if app.input_buffer.is_empty() {
-            "7x9y2z8w4v5q1r3t6u0o2jhbjhb2jh12nvc1h2".to_string()
+        // Masked input field with placeholder
+        let placeholder = if app.vault_locked {
+            "passphrase"
         } else {
-            app.input_buffer.as_str().to_string()
+            "7x9y2z8w4v5q1r3t6u0o2jhbjhb2jh12nvc1h2"
         };
-        
-        let input = Paragraph::new(display_text)
+        let input = Paragraph::new(app.input_buffer.line(Span::raw(placeholder)))
             .block(
                 Block::bordered()
-                    .title(" API Key ")
+                    .title(field)
                     .title_alignment(Alignment::Center)
                     .style(Style::default().fg(
                         if app.input_buffer.is_empty() {
-                            Color::DarkGray
+                            theme.muted
+                        } else if app.key_error.is_some() {
+                            theme.error
+                        } else if app.key_check.is_some() {
+                            theme.accent
                         } else if app.is_valid_api_key() {
-                            Color::LightGreen
+                            theme.success
                         } else {
-                            Color::LightRed
+                            theme.error
                         }
                     ))
             )
             .style(Style::default().fg(
                 if app.input_buffer.is_empty() {
-                    Color::DarkGray
+                    theme.muted
                 } else {
-                    Color::White
+                    theme.text
                 }
             ))
             .alignment(Alignment::Center);
         frame.render_widget(input, layout[1]);
 
-        // Instructions
-        let instructions = if app.input_buffer.is_empty() {
-            "Enter your API key"
+        // Instructions, or the progress and outcome of checking the key
+        let instructions = if let Some(check) = &app.key_check {
+            let frame_index = check.started.elapsed().as_millis() / 80;
+            let spinner = SPINNER[frame_index as usize % SPINNER.len()];
+            format!("{} Checking the key with Todoist… (esc to cancel)", spinner)
+        } else if let Some(error) = &app.key_error {
+            error.to_string()
+        } else if app.input_buffer.is_empty() && app.vault_locked {
+            "Enter your vault passphrase".to_string()
+        } else if app.input_buffer.is_empty() {
+            "Enter your API key".to_string()
         } else if !app.is_valid_api_key() {
-            "API key cannot be empty"
+            "API key cannot be empty".to_string()
         } else {
-            "Press Enter to continue"
+            "Press Enter to continue".to_string()
         };
         
         frame.render_widget(
@@ -78,82 +122,886 @@ pub fn render(app: &mut App, frame: &mut Frame) {
                 .block(
                     Block::bordered()
                         .style(Style::default().fg(
-                            if app.input_buffer.is_empty() {
-                                Color::LightMagenta
+                            if app.key_error.is_some() {
+                                theme.error
+                            } else if app.input_buffer.is_empty() || app.key_check.is_some() {
+                                theme.accent
                             } else if !app.is_valid_api_key() {
-                                Color::Red
+                                theme.error
                             } else {
-                                Color::LightGreen
+                                theme.success
                             }
                         ))
                 )
                 .alignment(Alignment::Center)
-                .style(Style::default().fg(Color::White)),
+                .style(theme.text_style()),
             layout[2],
         );
+        app.rendered = RenderedAreas::default();
+        render_toasts(app, frame);
         return;
     }
 
-    // Create layout
-    let layout = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Percentage(70),  // Today list
-            Constraint::Percentage(30),  // Inbox list
-        ])
-        .split(frame.area());
+    // Reserve the bottom lines for the sync status and for key hints, the
+    // command line and messages
+    let [main_area, status_area, footer_area] = Layout::vertical([
+        Constraint::Min(0),
+        Constraint::Length(1),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    let focused = app.focused_pane();
+    let (tabs_area, panes) = pane_areas(
+        &app.layout,
+        &app.panes,
+        app.layout_mode,
+        focused,
+        main_area,
+    );
+
+    let mut rendered = RenderedAreas::default();
+    if let Some(tabs_area) = tabs_area {
+        let titles = [Pane::Today, Pane::Inbox].map(|pane| pane.title());
+        // Each tab is its title padded by a space on both sides, followed by
+        // a one column divider
+        let mut x = tabs_area.x;
+        for (pane, title) in [Pane::Today, Pane::Inbox].into_iter().zip(titles) {
+            let width = title.chars().count() as u16 + 2;
+            rendered
+                .tabs
+                .push((pane, Rect::new(x, tabs_area.y, width, 1).intersection(tabs_area)));
+            x = x.saturating_add(width + 1);
+        }
+        let selected = if focused == Pane::Today { 0 } else { 1 };
+        frame.render_widget(
+            Tabs::new(titles)
+                .select(selected)
+                .style(theme.muted_style())
+                .highlight_style(theme.accent_style().add_modifier(Modifier::BOLD)),
+            tabs_area,
+        );
+    }
+
+    let marked = app.marked_task_ids();
+    let date_format = &app.settings.date_format;
+    for (pane, area) in panes {
+        let (tasks, empty) = match pane {
+            Pane::Today => (app.today_tasks(), "No tasks for Today"),
+            Pane::Inbox => (app.inbox_tasks(), "No tasks in Inbox"),
+        };
+        let count = tasks.len();
+        let items: Vec<ListItem> = if tasks.is_empty() {
+            vec![ListItem::new(empty)]
+        } else {
+            tasks
+                .iter()
+                .map(|task| task_item(theme, date_format, task, marked.contains(&task.id)))
+                .collect()
+        };
+        let title = if app.panes.zoomed {
+            format!("{} (zoomed)", pane.title())
+        } else {
+            pane.title().to_string()
+        };
+
+        let state = match pane {
+            Pane::Today => &mut app.today_list_state,
+            Pane::Inbox => &mut app.list_state,
+        };
+        // Where the cursor is in long lists, e.g. `12/240`
+        let position = match state.selected() {
+            Some(i) if count > 0 => format!(" {}/{} ", i + 1, count),
+            _ => format!(" {} ", count),
+        };
 
-    // Create Today list
-    let today_items: Vec<ListItem> = if app.today_tasks().is_empty() {
-        vec![ListItem::new("No tasks for Today")]
+        let list = List::new(items)
+            .block(
+                Block::bordered()
+                    .title(title)
+                    .title_bottom(Line::from(position).right_aligned())
+                    .border_style(theme.border_style()),
+            )
+            .style(theme.text_style())
+            .highlight_style(theme.selected_style())
+            .highlight_symbol(">> ")
+            .highlight_spacing(HighlightSpacing::Always);
+        frame.render_stateful_widget(list, area, state);
+
+        let rows = Block::bordered().inner(area);
+        let overflow = count.saturating_sub(rows.height as usize);
+        if overflow > 0 {
+            let mut scrollbar = ScrollbarState::new(overflow).position(state.offset());
+            frame.render_stateful_widget(
+                Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                    .begin_symbol(None)
+                    .end_symbol(None)
+                    .track_style(theme.border_style())
+                    .thumb_style(theme.accent_style()),
+                area.inner(Margin::new(0, 1)),
+                &mut scrollbar,
+            );
+        }
+        rendered.lists.push(ListArea {
+            pane,
+            area,
+            rows,
+            offset: state.offset(),
+            // After the highlight symbol and the mark indicator
+            checkbox_x: rows.x + 5,
+        });
+    }
+    app.rendered = rendered;
+
+    if app.calendar.is_some() {
+        render_calendar(app, frame, main_area);
+    }
+
+    if app.agenda.is_some() {
+        render_agenda(app, frame, main_area);
+    }
+
+    render_status_bar(app, frame, status_area);
+    render_footer(app, frame, footer_area);
+
+    if app.search.is_some() {
+        render_search(app, frame);
+    }
+
+    if app.palette.is_some() {
+        render_palette(app, frame);
+    }
+
+    if app.prompt.is_some() {
+        render_prompt(app, frame);
+    }
+
+    if app.show_help {
+        render_help(app, frame);
+    }
+
+    render_toasts(app, frame);
+}
+
+/// A task row: checkbox, content, priority and due date, highlighted while
+/// the task is marked for a bulk action.
+fn task_item(theme: &Theme, date_format: &str, task: &Task, marked: bool) -> ListItem<'static> {
+    let status_symbol = if task.is_completed { "✓" } else { "☐" };
+    let mut spans = vec![
+        Span::raw(if marked { "● " } else { "  " }),
+        Span::raw(format!("{} {}", status_symbol, task.content)),
+    ];
+    // Todoist's priority 4 is shown as p1; normal priority gets no tag
+    if task.priority > 1 {
+        spans.push(Span::styled(
+            format!(" p{}", 5 - task.priority),
+            Style::default().fg(theme.priority(task.priority)),
+        ));
+    }
+    if let Some(due) = &task.due {
+        let today = chrono::Local::now().date_naive().to_string();
+        // ISO dates compare chronologically as strings
+        let style = if due.date < today {
+            Style::default().fg(theme.overdue)
+        } else {
+            theme.muted_style()
+        };
+        let date = calendar::due_date(task)
+            .map_or_else(|| due.date.clone(), |date| date.format(date_format).to_string());
+        spans.push(Span::styled(format!(" ({})", date), style));
+    }
+
+    let item = ListItem::new(Line::from(spans));
+    if marked {
+        item.style(Style::default().fg(theme.marked))
     } else {
-        app.today_tasks()
+        item
+    }
+}
+
+/// Stacks toasts in the bottom-right corner above the status bar, newest at
+/// the bottom.
+fn render_toasts(app: &App, frame: &mut Frame) {
+    let theme = &app.theme;
+    let area = frame.area();
+    let width = area.width.min(48);
+    let mut bottom = area.bottom().saturating_sub(2);
+
+    for toast in app.toasts.iter().rev() {
+        if bottom < area.top() + 3 {
+            break;
+        }
+        let x = area.right().saturating_sub(width + 1);
+        let toast_area = Rect::new(x, bottom - 3, width, 3);
+        bottom -= 3;
+
+        let color = match toast.severity {
+            Severity::Info => theme.accent,
+            Severity::Success => theme.success,
+            Severity::Warning => theme.warning,
+            Severity::Error => theme.error,
+        };
+        let mut spans = vec![Span::styled(
+            toast.message.as_str(),
+            theme.text_style(),
+        )];
+        if let Some(action) = toast.action {
+            if let Some(keys) = app.keymap.keys_for(action).first() {
+                spans.push(Span::styled(
+                    format!("  [{}] {}", keys, action.hint()),
+                    Style::default().fg(color),
+                ));
+            }
+        }
+
+        frame.render_widget(Clear, toast_area);
+        frame.render_widget(
+            Paragraph::new(Line::from(spans))
+                .block(Block::bordered().border_style(Style::default().fg(color))),
+            toast_area,
+        );
+    }
+}
+
+/// Renders the month view over the task lists: a grid with the number of
+/// tasks due each day, colored by the most urgent one, and the tasks due on
+/// the selected day.
+fn render_calendar(app: &mut App, frame: &mut Frame, area: Rect) {
+    let theme = &app.theme;
+    let Some(calendar) = &mut app.calendar else {
+        return;
+    };
+    frame.render_widget(Clear, area);
+    let [grid_area, list_area] =
+        Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .areas(area);
+
+    let today = chrono::Local::now().date_naive();
+    let summaries = calendar::day_summaries(&app.tasks);
+    let weeks = calendar.weeks();
+    // Two-letter day names, e.g. "Mo", starting on the configured day
+    let header = Row::new(weeks[0].map(|date| date.format("%a").to_string()[..2].to_string()))
+        .style(theme.heading_style())
+        .bottom_margin(1);
+    let rows = weeks.into_iter().map(|week| {
+        Row::new(week.map(|date| {
+            let mut style = if date.month() == calendar.date.month() {
+                theme.text_style()
+            } else {
+                theme.muted_style()
+            };
+            if date == today {
+                style = style.fg(theme.accent).add_modifier(Modifier::BOLD);
+            }
+            let mut spans = vec![Span::styled(format!("{:>2}", date.day()), style)];
+            if let Some(summary) = summaries.get(&date) {
+                spans.push(Span::styled(
+                    format!(" {}", summary.count),
+                    Style::default().fg(theme.priority(summary.priority)),
+                ));
+            }
+            let cell = Cell::from(Line::from(spans));
+            if date == calendar.date {
+                cell.style(theme.selected_style())
+            } else {
+                cell
+            }
+        }))
+        .bottom_margin(1)
+    });
+
+    let moving = calendar
+        .moving
+        .as_deref()
+        .and_then(|id| app.tasks.iter().find(|task| task.id == id));
+    let mut block = Block::bordered()
+        .title(format!(" {} ", calendar.date.format("%B %Y")))
+        .title_alignment(Alignment::Center)
+        .border_style(theme.border_style());
+    if let Some(task) = moving {
+        block = block.title_bottom(
+            Line::styled(
+                format!(" Moving “{}”: pick a day, enter to drop ", task.content),
+                Style::default().fg(theme.marked),
+            )
+            .centered(),
+        );
+    }
+    let grid = Table::new(rows, [Constraint::Ratio(1, 7); 7])
+        .header(header)
+        .block(block)
+        .style(theme.text_style());
+    frame.render_widget(grid, grid_area);
+
+    let tasks = calendar::tasks_on(&app.tasks, calendar.date);
+    let items: Vec<ListItem> = if tasks.is_empty() {
+        vec![ListItem::new("No tasks due")]
+    } else {
+        tasks
             .iter()
-            .filter(|task| !task.is_completed)
             .map(|task| {
-                let status_symbol = if task.is_completed { "✓" } else { "☐" };
-                let content = if let Some(due) = &task.due {
-                    format!("{} {} ({})", status_symbol, task.content, due.date)
-                } else {
-                    format!("{} {}", status_symbol, task.content)
-                };
-                ListItem::new(content)
+                let moving = calendar.moving.as_ref() == Some(&task.id);
+                task_item(theme, &app.settings.date_format, task, moving)
             })
             .collect()
     };
-
-    let today_list = List::new(today_items)
-        .block(Block::bordered().title("Today"))
-        .style(Style::default().fg(Color::White))
-        .highlight_style(Style::default().bg(Color::DarkGray))
+    let list = List::new(items)
+        .block(
+            Block::bordered()
+                .title(format!(" {} ", calendar.date.format("%A, %-d %B")))
+                .border_style(theme.border_style()),
+        )
+        .style(theme.text_style())
+        .highlight_style(theme.selected_style())
         .highlight_symbol(">> ");
+    frame.render_stateful_widget(list, list_area, &mut calendar.list_state);
+}
 
-    // Create Inbox list
-    let inbox_items: Vec<ListItem> = if app.tasks.is_empty() {
-        vec![ListItem::new("No tasks in Inbox")]
-    } else {
-        app.tasks
+/// Renders the agenda over the task lists: the selected day as a half-hourly
+/// timeline beside its week, with timed tasks as blocks as long as their
+/// duration. Overlapping blocks and the current time stand out.
+fn render_agenda(app: &mut App, frame: &mut Frame, area: Rect) {
+    let theme = &app.theme;
+    let Some(agenda) = &mut app.agenda else {
+        return;
+    };
+    frame.render_widget(Clear, area);
+    let [day_area, week_area] =
+        Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+            .areas(area);
+
+    // Borders and the header row
+    agenda.visible_slots = (area.height as usize).saturating_sub(3).max(1);
+    agenda.scroll(0);
+    let slots = agenda.first_slot..(agenda.first_slot + agenda.visible_slots)
+        .min(agenda::SLOTS_PER_DAY);
+
+    let now = chrono::Local::now().naive_local();
+    let now_slot = agenda::slot_of(now.time());
+    let time_cell = |date: chrono::NaiveDate, slot: usize| {
+        let (start, _) = agenda::slot_range(date, slot);
+        let label = if start.minute() == 0 {
+            start.format("%H:%M").to_string()
+        } else {
+            String::new()
+        };
+        if date == now.date() && slot == now_slot {
+            Cell::from(format!("{:>5}▶", now.format("%H:%M")))
+                .style(Style::default().fg(theme.error))
+        } else {
+            Cell::from(label).style(theme.muted_style())
+        }
+    };
+    // Blocks in a row: their titles where they start, a bar below
+    let block_cell = |blocks: &[&TimeBlock], date, slot: usize| {
+        let (start, _) = agenda::slot_range(date, slot);
+        let text = blocks
             .iter()
-            .filter(|task| !task.is_completed)
-            .map(|task| {
-                let status_symbol = if task.is_completed { "✓" } else { "☐" };
-                let content = if let Some(due) = &task.due {
-                    format!("{} {} ({})", status_symbol, task.content, due.date)
+            .map(|block| {
+                if block.start >= start || slot == agenda.first_slot {
+                    format!("{} {}", block.start.format("%H:%M"), block.task.content)
                 } else {
-                    format!("{} {}", status_symbol, task.content)
-                };
-                ListItem::new(content)
+                    "┆".to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" │ ");
+        let color = if blocks.iter().any(|block| block.overlaps) {
+            theme.warning
+        } else {
+            match blocks.iter().map(|block| block.task.priority).max() {
+                Some(priority) if priority > 1 => theme.priority(priority),
+                _ => theme.accent,
+            }
+        };
+        Cell::from(text).style(Style::default().fg(color).add_modifier(Modifier::REVERSED))
+    };
+
+    // Timeline of the selected day
+    let day_blocks = agenda::blocks_on(&app.tasks, agenda.date);
+    let all_day: Vec<&str> = calendar::tasks_on(&app.tasks, agenda.date)
+        .into_iter()
+        .filter(|task| agenda::due_datetime(task).is_none())
+        .map(|task| task.content.as_str())
+        .collect();
+    let header = Row::new([
+        Cell::from(""),
+        Cell::from(if all_day.is_empty() {
+            String::new()
+        } else {
+            format!("All day: {}", all_day.join(", "))
+        }),
+    ])
+    .style(theme.muted_style());
+    let rows = slots.clone().map(|slot| {
+        let (start, end) = agenda::slot_range(agenda.date, slot);
+        let blocks: Vec<&TimeBlock> = day_blocks
+            .iter()
+            .filter(|block| block.covers(start, end))
+            .collect();
+        let cell = if blocks.is_empty() {
+            Cell::from("")
+        } else {
+            block_cell(&blocks, agenda.date, slot)
+        };
+        Row::new([time_cell(agenda.date, slot), cell])
+    });
+    let day = Table::new(rows, [Constraint::Length(6), Constraint::Min(0)])
+        .header(header)
+        .block(
+            Block::bordered()
+                .title(format!(" {} ", agenda.date.format("%A, %-d %B")))
+                .border_style(theme.border_style()),
+        )
+        .style(theme.text_style());
+    frame.render_widget(day, day_area);
+
+    // The week around it, a column per day
+    let week = agenda.week();
+    let week_blocks = week.map(|date| agenda::blocks_on(&app.tasks, date));
+    let header = Row::new(std::iter::once(Cell::from("")).chain(week.iter().map(|date| {
+        let style = if *date == agenda.date {
+            theme.accent_style().add_modifier(Modifier::BOLD)
+        } else {
+            theme.heading_style()
+        };
+        Cell::from(date.format("%a %-d").to_string()).style(style)
+    })));
+    // The time column only marks the current time in the current week
+    let time_date = if week.contains(&now.date()) {
+        now.date()
+    } else {
+        week[0]
+    };
+    let rows = slots.map(|slot| {
+        let cells = week.iter().zip(&week_blocks).map(|(date, day_blocks)| {
+            let (start, end) = agenda::slot_range(*date, slot);
+            let blocks: Vec<&TimeBlock> = day_blocks
+                .iter()
+                .filter(|block| block.covers(start, end))
+                .collect();
+            if !blocks.is_empty() {
+                block_cell(&blocks, *date, slot)
+            } else if *date == now.date() && slot == now_slot {
+                Cell::from("────").style(Style::default().fg(theme.error))
+            } else {
+                Cell::from("")
+            }
+        });
+        Row::new(std::iter::once(time_cell(time_date, slot)).chain(cells))
+    });
+    let mut widths = vec![Constraint::Length(6)];
+    widths.extend([Constraint::Ratio(1, 7); 7]);
+    let grid = Table::new(rows, widths)
+        .header(header)
+        .block(
+            Block::bordered()
+                .title(format!(" Week of {} ", week[0].format("%-d %B")))
+                .border_style(theme.border_style()),
+        )
+        .style(theme.text_style());
+    frame.render_widget(grid, week_area);
+}
+
+/// Renders the task prompt: the input and, below it, what it resolves to.
+fn render_prompt(app: &App, frame: &mut Frame) {
+    let theme = &app.theme;
+    let Some(prompt) = &app.prompt else {
+        return;
+    };
+    let [area] = Layout::vertical([Constraint::Length(4)])
+        .flex(Flex::Center)
+        .areas(frame.area());
+    let [area] = Layout::horizontal([Constraint::Percentage(60)])
+        .flex(Flex::Center)
+        .areas(area);
+    frame.render_widget(Clear, area);
+
+    let input_line = prompt
+        .input
+        .line(Span::styled(prompt.kind.placeholder(), theme.muted_style()));
+    let input = prompt.input.value().trim();
+    let preview = match prompt.kind {
+        PromptKind::EditContent | PromptKind::AddLabel => String::new(),
+        PromptKind::SetPriority => match parse_priority(input) {
+            Some(priority) => format!("→ p{}", 5 - priority),
+            None => "→ enter 1 to 4".to_string(),
+        },
+        PromptKind::Reschedule if input.is_empty() => "→ no date".to_string(),
+        PromptKind::Reschedule => {
+            match parse_due_date(input, chrono::Local::now().date_naive()) {
+                Some(date) => format!("→ {}", date),
+                None => "→ left to Todoist to interpret".to_string(),
+            }
+        }
+        PromptKind::MoveToProject => match match_project(input, &app.projects) {
+            Some(project) => format!("→ #{}", project.name),
+            None => "→ no matching project".to_string(),
+        },
+        PromptKind::SwitchProfile => {
+            let names = app.profile.names();
+            if input.is_empty() {
+                format!("→ {}", names.join(", "))
+            } else if input == app.profile.name {
+                "→ current profile".to_string()
+            } else if names.iter().any(|name| name == input) {
+                format!("→ switch to {}", input)
+            } else {
+                format!("→ create {}", input)
+            }
+        }
+    };
+
+    let title = match prompt.task_ids.len() {
+        0 | 1 => format!(" {} ", prompt.kind.title()),
+        n => format!(" {} ({} tasks) ", prompt.kind.title(), n),
+    };
+    frame.render_widget(
+        Paragraph::new(vec![
+            input_line,
+            Line::styled(preview, theme.muted_style()),
+        ])
+        .block(Block::bordered().border_style(theme.border_style()).title(title))
+        .style(theme.text_style()),
+        area,
+    );
+}
+
+/// Renders the command palette: a query line and every matching action with
+/// its current key bindings.
+fn render_palette(app: &mut App, frame: &mut Frame) {
+    let theme = &app.theme;
+    let area = popup_area(frame.area(), 60, 60);
+    frame.render_widget(Clear, area);
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0)])
+        .split(area);
+
+    let keymap = &app.keymap;
+    let Some(palette) = &mut app.palette else {
+        return;
+    };
+
+    frame.render_widget(
+        Paragraph::new(input_line("> ", &palette.query))
+            .block(Block::bordered().border_style(theme.border_style()).title(" Command palette "))
+            .style(theme.text_style()),
+        layout[0],
+    );
+
+    let muted = theme.muted_style();
+    let items: Vec<ListItem> = if palette.entries.is_empty() {
+        vec![ListItem::new("No matching actions").style(muted)]
+    } else {
+        palette
+            .entries
+            .iter()
+            .map(|entry| {
+                let indices = entry
+                    .matched
+                    .as_ref()
+                    .map(|m| m.indices.as_slice())
+                    .unwrap_or_default();
+                let mut spans = highlight(theme, entry.action.description(), indices);
+                let keys = keymap.keys_for(entry.action);
+                if !keys.is_empty() {
+                    spans.push(Span::styled(format!("  {}", keys.join(", ")), muted));
+                }
+                ListItem::new(Line::from(spans))
             })
             .collect()
     };
 
-    let inbox_list = List::new(inbox_items)
-        .block(Block::bordered().title("Inbox"))
-        .style(Style::default().fg(Color::White))
-        .highlight_style(Style::default().bg(Color::DarkGray))
+    let list = List::new(items)
+        .block(Block::bordered().border_style(theme.border_style()))
+        .style(theme.text_style())
+        .highlight_style(theme.selected_style())
         .highlight_symbol(">> ");
+    frame.render_stateful_widget(list, layout[1], &mut palette.list_state);
+}
+
+/// Shows whether Todoist is reachable, how many changes wait to be sent,
+/// when tasks were last refreshed and the latest sync error.
+fn render_status_bar(app: &App, frame: &mut Frame, area: Rect) {
+    let theme = &app.theme;
+    let muted = theme.muted_style();
+    let status = &app.sync_status;
+    let (indicator, color) = match status.online {
+        None => ("○ connecting", theme.warning),
+        Some(true) => ("● online", theme.success),
+        Some(false) => ("● offline", theme.error),
+    };
+
+    let pending = app.pending_changes.len();
+    let mut spans = vec![
+        Span::styled(format!(" {} ", indicator), Style::default().fg(color)),
+        Span::styled("│ ", muted),
+        Span::styled(
+            format!("{} pending ", pending),
+            if pending > 0 {
+                Style::default().fg(theme.warning)
+            } else {
+                muted
+            },
+        ),
+        Span::styled("│ ", muted),
+        Span::styled(
+            match status.last_refresh {
+                Some(at) => format!("refreshed {} ", format_elapsed(at.elapsed())),
+                None => "not refreshed yet ".to_string(),
+            },
+            muted,
+        ),
+    ];
+    let marked = app.marked_task_ids().len();
+    if marked > 0 || app.visual_anchor.is_some() {
+        spans.push(Span::styled("│ ", muted));
+        let range = if app.visual_anchor.is_some() {
+            " (selecting range)"
+        } else {
+            ""
+        };
+        spans.push(Span::styled(
+            format!("{} marked{} ", marked, range),
+            Style::default().fg(theme.marked),
+        ));
+    }
+    if let Some((error, at)) = &status.last_error {
+        spans.push(Span::styled("│ ", muted));
+        let error = error.lines().next().unwrap_or_default();
+        spans.push(Span::styled(
+            format!("{} ({})", error, format_elapsed(at.elapsed())),
+            Style::default().fg(theme.error),
+        ));
+    }
+
+    frame.render_widget(
+        Paragraph::new(Line::from(spans)).style(Style::default().bg(theme.status_bar)),
+        area,
+    );
+}
+
+/// Formats a duration as a short relative time, e.g. `12s ago`.
+fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    match secs {
+        0..=4 => "just now".to_string(),
+        5..=59 => format!("{}s ago", secs),
+        60..=3599 => format!("{}m ago", secs / 60),
+        _ => format!("{}h ago", secs / 3600),
+    }
+}
+
+/// Renders the `:` command line, the latest message or the key hints of the
+/// current mode, with the count and chord typed so far on the right like
+/// vim's `showcmd`.
+fn render_footer(app: &App, frame: &mut Frame, area: Rect) {
+    let theme = &app.theme;
+    let muted = theme.muted_style();
+    let left = if let Some(command) = &app.command_line {
+        input_line(":", command).style(theme.text_style())
+    } else if let Some(message) = &app.message {
+        Line::styled(message.as_str(), Style::default().fg(theme.warning))
+    } else {
+        let mut spans = Vec::new();
+        for (keys, label) in app.mode().hints(&app.keymap) {
+            if !spans.is_empty() {
+                spans.push(Span::raw("  "));
+            }
+            spans.push(Span::styled(keys, theme.accent_style()));
+            spans.push(Span::styled(format!(" {}", label), muted));
+        }
+        Line::from(spans)
+    };
+    frame.render_widget(Paragraph::new(left), area);
+
+    let mut pending = app.count.map(|n| n.to_string()).unwrap_or_default();
+    pending.push_str(&format_sequence(&app.pending_keys).replace(' ', ""));
+    frame.render_widget(
+        Paragraph::new(pending)
+            .alignment(Alignment::Right)
+            .style(muted),
+        area,
+    );
+}
+
+/// Lists every action with the keys currently bound to it.
+fn render_help(app: &App, frame: &mut Frame) {
+    let theme = &app.theme;
+    let area = popup_area(frame.area(), 60, 80);
+    frame.render_widget(Clear, area);
+
+    let key_style = theme.accent_style();
+    let heading = |mode: Mode| {
+        Row::new(vec![Cell::from(mode.title())])
+            .style(theme.heading_style())
+    };
+
+    let mut rows = vec![heading(Mode::List)];
+    rows.extend(Action::ALL.iter().map(|action| {
+        let keys = app.keymap.keys_for(*action);
+        let keys = if keys.is_empty() {
+            "unbound".to_string()
+        } else {
+            keys.join(", ")
+        };
+        Row::new(vec![
+            Cell::from(keys).style(key_style),
+            Cell::from(action.description()),
+        ])
+    }));
+
+    for mode in [
+        Mode::Search,
+        Mode::Palette,
+        Mode::Prompt,
+        Mode::CommandLine,
+        Mode::Calendar,
+        Mode::Agenda,
+    ] {
+        rows.push(Row::new(vec![Cell::from("")]));
+        rows.push(heading(mode));
+        rows.extend(mode.hints(&app.keymap).into_iter().map(|(keys, label)| {
+            Row::new(vec![Cell::from(keys).style(key_style), Cell::from(label)])
+        }));
+    }
+
+    rows.push(Row::new(vec![Cell::from("")]));
+    rows.push(Row::new(vec![Cell::from("Text fields")]).style(theme.heading_style()));
+    rows.extend(input::edit_hints().into_iter().map(|(keys, label)| {
+        Row::new(vec![Cell::from(keys).style(key_style), Cell::from(label)])
+    }));
+
+    let table = Table::new(rows, [Constraint::Percentage(35), Constraint::Percentage(65)])
+        .block(
+            Block::bordered()
+                .title(" Key bindings ")
+                .title_alignment(Alignment::Center)
+                .title_bottom(Line::from(" press any key to close ").centered()),
+        )
+        .style(theme.text_style());
+    frame.render_widget(table, area);
+}
+
+fn render_search(app: &mut App, frame: &mut Frame) {
+    let theme = &app.theme;
+    let area = popup_area(frame.area(), 70, 60);
+    frame.render_widget(Clear, area);
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0)])
+        .split(area);
+
+    let Some(search) = &mut app.search else {
+        return;
+    };
+
+    frame.render_widget(
+        Paragraph::new(input_line("/", &search.query))
+            .block(Block::bordered().border_style(theme.border_style()).title(" Search "))
+            .style(theme.text_style()),
+        layout[0],
+    );
+
+    let items: Vec<ListItem> = if search.results.is_empty() {
+        let message = if search.query.is_empty() {
+            "Type to search tasks, labels and projects"
+        } else {
+            "No matching tasks"
+        };
+        vec![ListItem::new(message).style(theme.muted_style())]
+    } else {
+        search_items(theme, &app.tasks, &app.projects, &search.results)
+    };
+
+    let results = List::new(items)
+        .block(
+            Block::bordered()
+                .border_style(theme.border_style())
+                .title(format!(" {} results ", search.results.len())),
+        )
+        .style(theme.text_style())
+        .highlight_style(theme.selected_style())
+        .highlight_symbol(">> ");
+
+    frame.render_stateful_widget(results, layout[1], &mut search.list_state);
+}
+
+fn search_items<'a>(
+    theme: &Theme,
+    tasks: &'a [Task],
+    projects: &'a [Project],
+    results: &'a [SearchResult],
+) -> Vec<ListItem<'a>> {
+    let muted = theme.muted_style();
+    results
+        .iter()
+        .filter_map(|result| {
+            let task = tasks.iter().find(|task| task.id == result.task_id)?;
+            let indices = &result.matched.indices;
+            let mut spans = vec![Span::raw("☐ ")];
+            match &result.field {
+                MatchField::Content => spans.extend(highlight(theme, &task.content, indices)),
+                MatchField::Description => {
+                    spans.push(Span::raw(task.content.as_str()));
+                    spans.push(Span::styled("  · ", muted));
+                    spans.extend(highlight(theme, &task.description, indices));
+                }
+                MatchField::Label(label) => {
+                    spans.push(Span::raw(task.content.as_str()));
+                    spans.push(Span::styled("  @", muted));
+                    spans.extend(highlight(theme, label, indices));
+                }
+                MatchField::Project(project) => {
+                    spans.push(Span::raw(task.content.as_str()));
+                    spans.push(Span::styled("  #", muted));
+                    spans.extend(highlight(theme, project, indices));
+                }
+            }
+            if !matches!(result.field, MatchField::Project(_)) {
+                if let Some(project) = projects.iter().find(|p| p.id == task.project_id) {
+                    spans.push(Span::styled(format!("  #{}", project.name), muted));
+                }
+            }
+            Some(ListItem::new(Line::from(spans)))
+        })
+        .collect()
+}
+
+/// Splits `text` into spans, emphasising the chars at the given char indices.
+fn highlight<'a>(theme: &Theme, text: &'a str, indices: &[usize]) -> Vec<Span<'a>> {
+    let matched = Style::default()
+        .fg(theme.highlight)
+        .add_modifier(Modifier::BOLD);
+    text.chars()
+        .enumerate()
+        .map(|(i, c)| {
+            if indices.contains(&i) {
+                Span::styled(c.to_string(), matched)
+            } else {
+                Span::raw(c.to_string())
+            }
+        })
+        .collect()
+}
+
+/// Returns a rectangle centered in `area` taking the given percentages of it.
+fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
+    let [area] = Layout::vertical([Constraint::Percentage(percent_y)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::horizontal([Constraint::Percentage(percent_x)])
+        .flex(Flex::Center)
+        .areas(area);
+    area
+}
 
-    // Render both lists
-    frame.render_stateful_widget(today_list, layout[0], &mut app.today_list_state);
-    frame.render_stateful_widget(inbox_list, layout[1], &mut app.list_state);
+/// A text field after a fixed prefix such as `/` or `:`.
+fn input_line(prefix: &'static str, input: &TextInput) -> Line<'static> {
+    let mut line = input.line(Span::raw(""));
+    line.spans.insert(0, Span::raw(prefix));
+    line
 }